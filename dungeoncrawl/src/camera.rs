@@ -1,5 +1,43 @@
 use crate::prelude::*;
 
+// How the camera follows the player as they move around the map
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraMode {
+    // Always centered on the player, scrolling past the edges of the map
+    Centered,
+    // Centered on the player but never showing anything beyond the edges of the map
+    Clamped,
+    // Only scrolls once the player leaves the area around the center of the screen
+    DeadZone,
+    // Moves a whole screen at a time once the player walks off the edge of the screen
+    PageFlip,
+}
+
+impl CameraMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraMode::Centered => "Centered",
+            CameraMode::Clamped => "Clamped",
+            CameraMode::DeadZone => "Dead zone",
+            CameraMode::PageFlip => "Page flip",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            CameraMode::Centered => CameraMode::Clamped,
+            CameraMode::Clamped => CameraMode::DeadZone,
+            CameraMode::DeadZone => CameraMode::PageFlip,
+            CameraMode::PageFlip => CameraMode::Centered,
+        }
+    }
+}
+
+// The distance from the edges of the screen the player can move in a dead zone camera
+// before it scrolls
+const DEAD_ZONE_MARGIN_X: i32 = DISPLAY_WIDTH / 4;
+const DEAD_ZONE_MARGIN_Y: i32 = DISPLAY_HEIGHT / 4;
+
 pub struct Camera {
     pub left_x: i32,
     pub right_x: i32,
@@ -8,19 +46,98 @@ pub struct Camera {
 }
 
 impl Camera {
-    pub fn new(player_position: Point) -> Self {
-        Self {
-            left_x: player_position.x - DISPLAY_WIDTH / 2,
-            right_x: player_position.x + DISPLAY_WIDTH / 2,
-            top_y: player_position.y - DISPLAY_HEIGHT / 2,
-            bottom_y: player_position.y + DISPLAY_HEIGHT / 2,
+    pub fn new(player_position: Point, mode: CameraMode, map: &Map) -> Self {
+        let mut camera = Self {
+            left_x: 0,
+            right_x: DISPLAY_WIDTH,
+            top_y: 0,
+            bottom_y: DISPLAY_HEIGHT,
+        };
+        match mode {
+            CameraMode::PageFlip => camera.flip_to_page(player_position),
+            _ => camera.center_on(player_position),
         }
+        if mode != CameraMode::Centered {
+            camera.clamp(map);
+        }
+        camera
+    }
+
+    pub fn on_player_move(&mut self, player_position: Point, mode: CameraMode, map: &Map) {
+        match mode {
+            CameraMode::Centered | CameraMode::Clamped => self.center_on(player_position),
+            CameraMode::DeadZone => self.follow_dead_zone(player_position),
+            CameraMode::PageFlip => {
+                if !self.contains(player_position) {
+                    self.flip_to_page(player_position);
+                }
+            }
+        }
+        if mode != CameraMode::Centered {
+            self.clamp(map);
+        }
+    }
+
+    /*
+     * Check if the point on the map is within the viewport
+     */
+    pub fn contains(&self, point: Point) -> bool {
+        point.x >= self.left_x
+            && point.x < self.right_x
+            && point.y >= self.top_y
+            && point.y < self.bottom_y
+    }
+
+    fn move_to(&mut self, left_x: i32, top_y: i32) {
+        self.left_x = left_x;
+        self.right_x = left_x + DISPLAY_WIDTH;
+        self.top_y = top_y;
+        self.bottom_y = top_y + DISPLAY_HEIGHT;
+    }
+
+    fn center_on(&mut self, point: Point) {
+        self.move_to(point.x - DISPLAY_WIDTH / 2, point.y - DISPLAY_HEIGHT / 2);
+    }
+
+    /*
+     * Keep the viewport within the map. A map smaller than the viewport is centered.
+     */
+    fn clamp(&mut self, map: &Map) {
+        let clamp_axis = |start: i32, map_size: i32, display_size: i32| {
+            if map_size < display_size {
+                (map_size - display_size) / 2
+            } else {
+                start.clamp(0, map_size - display_size)
+            }
+        };
+        self.move_to(
+            clamp_axis(self.left_x, map.width, DISPLAY_WIDTH),
+            clamp_axis(self.top_y, map.height, DISPLAY_HEIGHT),
+        );
+    }
+
+    /*
+     * Scroll just far enough to bring the point back inside the dead zone
+     */
+    fn follow_dead_zone(&mut self, point: Point) {
+        let left_x = self.left_x.clamp(
+            point.x - DISPLAY_WIDTH + DEAD_ZONE_MARGIN_X + 1,
+            point.x - DEAD_ZONE_MARGIN_X,
+        );
+        let top_y = self.top_y.clamp(
+            point.y - DISPLAY_HEIGHT + DEAD_ZONE_MARGIN_Y + 1,
+            point.y - DEAD_ZONE_MARGIN_Y,
+        );
+        self.move_to(left_x, top_y);
     }
 
-    pub fn on_player_move(&mut self, player_position: Point) {
-        self.left_x = player_position.x - DISPLAY_WIDTH / 2;
-        self.right_x = player_position.x + DISPLAY_WIDTH / 2;
-        self.top_y = player_position.y - DISPLAY_HEIGHT / 2;
-        self.bottom_y = player_position.y + DISPLAY_HEIGHT / 2;
+    /*
+     * Show the screen sized page of the map the point is on
+     */
+    fn flip_to_page(&mut self, point: Point) {
+        self.move_to(
+            point.x.div_euclid(DISPLAY_WIDTH) * DISPLAY_WIDTH,
+            point.y.div_euclid(DISPLAY_HEIGHT) * DISPLAY_HEIGHT,
+        );
     }
 }
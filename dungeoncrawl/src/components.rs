@@ -1,55 +1,352 @@
 pub use crate::prelude::*;
 
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Render {
     pub color: ColorPair,
     pub glyph: FontCharType,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Player;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Enemy;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/*
+ * The monster guarding the Amulet of Yala. It calls goblins to its aid every few turns
+ * once it is fighting, and enrages when its health drops to half, speeding up and
+ * calling for aid more often.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Boss {
+    pub enraged: bool,
+    // The turns the boss acts before it summons again
+    pub summon_cooldown: i32,
+}
+
+// The turns the boss acts between summoning goblins
+pub const BOSS_SUMMON_INTERVAL: i32 = 6;
+
+impl Boss {
+    // The turns until the next summoning, which come twice as often once enraged
+    pub fn summon_interval(&self) -> i32 {
+        if self.enraged {
+            BOSS_SUMMON_INTERVAL / 2
+        } else {
+            BOSS_SUMMON_INTERVAL
+        }
+    }
+}
+
+/*
+ * The side a creature is on. How creatures treat each other, whether they attack, leave
+ * each other alone or fight side by side, is decided by their factions.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Faction {
+    // The player and their companions
+    Player,
+    // Goblins and orcs
+    Goblins,
+    // Bats and other creatures living in the dungeon
+    Wildlife,
+    Undead,
+    // Merchants
+    Traders,
+}
+
+// A friendly creature that follows the player and fights the monsters at their side
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Companion;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MovingRandomly;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WantsToMove {
     pub entity: Entity,
     pub destination: Point,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+// Message sent to spend a turn digging into the wall at the position
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WantsToDig {
+    pub digger: Entity,
+    pub position: Point,
+    // The turns it takes the digger to dig through a wall
+    pub turns: i32,
+}
+
+/*
+ * Digs through walls in the given number of turns. On an item, such as a pickaxe, it lets
+ * whoever carries the item dig. On a creature it makes the creature tunnel through the
+ * walls in its way.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Digger {
+    pub turns: i32,
+}
+
+// The wall an entity is digging into and the turns spent on it so far
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Digging {
+    pub position: Point,
+    pub progress: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Health {
     pub current: i32,
     pub max: i32,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Name {
     pub value: String,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+impl Name {
+    /*
+     * The name the player knows the entity by. Unidentified items are shown by their
+     * appearance rather than their name.
+     */
+    pub fn shown(&self, unidentified: Option<&Unidentified>) -> String {
+        match unidentified {
+            Some(unidentified) => unidentified.appearance.clone(),
+            None => self.value.clone(),
+        }
+    }
+}
+
+// An item the player hasn't identified yet. Every item of a kind shares an appearance.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Unidentified {
+    pub appearance: String,
+}
+
+/*
+ * A message of intent to interact with a non hostile entity, sent by bumping into it
+ * rather than attacking it.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WantsToInteract {
+    pub actor: Entity,
+    pub target: Entity,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WantsToAttack {
     pub attacker: Entity,
     pub victim: Entity,
+    pub damage: i32,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ChasingPlayer;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Item;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AmuletOfYala;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Carried(pub Entity);
+
+// The gold carried by an entity, or lying in a pile on the map
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Gold {
+    pub amount: i32,
+}
+
+// The gold a merchant asks for an item. Items are sold back for half of their price.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Price {
+    pub value: i32,
+}
+
+impl Price {
+    pub fn sell_value(&self) -> i32 {
+        self.value / 2
+    }
+}
+
+// A shopkeeper that trades items for gold when bumped into
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Merchant;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActivateItem {
+    pub used_by: Entity,
+    pub item: Entity,
+    // The tile targeted by items and spells that are used at range
+    pub target: Option<Point>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesNutrition {
+    pub amount: i32,
+}
+
+// Hunger states are ordered from least to most severe
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum HungerState {
+    WellFed,
+    Hungry,
+    Weak,
+    Starving,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Hunger {
+    pub current: i32,
+    pub max: i32,
+}
+
+impl Hunger {
+    pub fn new(max: i32) -> Self {
+        Self { current: max, max }
+    }
+
+    /*
+     * The hunger state is derived from how much of the hunger clock remains. A well fed
+     * entity has more than half of its clock left, a starving entity has none.
+     */
+    pub fn state(&self) -> HungerState {
+        if self.current > self.max / 2 {
+            HungerState::WellFed
+        } else if self.current > self.max / 6 {
+            HungerState::Hungry
+        } else if self.current > 0 {
+            HungerState::Weak
+        } else {
+            HungerState::Starving
+        }
+    }
+}
+
+/*
+ * Reveals the map tiles within the radius of the user. A radius of zero reveals the whole
+ * map.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesMagicMapping {
+    pub radius: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesDetectMonsters {
+    pub turns: i32,
+}
+
+// Turns the targeted creature into a companion of the user
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesCharm;
+
+// Identifies every item carried by the user
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesIdentify;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesDamage {
+    pub amount: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesHealing {
+    pub amount: i32,
+}
+
+// Moves the user to the targeted tile
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesBlink;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesFear {
+    pub turns: i32,
+}
+
+// Teaches the spell with the given name to the user
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TeachesSpell {
+    pub name: String,
+}
+
+// Items and spells with a range are used on a targeted tile within the range
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ranged {
+    pub range: i32,
+}
+
+// Targeted effects hit every tile within the radius of the target that it can see
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AreaOfEffect {
+    pub radius: i32,
+}
+
+// Targeted effects hit every tile on the line from the user to the target
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LineOfEffect;
+
+// An item that can be used a limited number of times before it is used up
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Charges {
+    pub current: i32,
+    pub max: i32,
+}
+
+/*
+ * A spell. Spells share their effects with items, but are cast with mana by the entities
+ * that have learned them rather than being used up.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Spell {
+    pub mana_cost: i32,
+}
+
+// The entity that has learned the spell
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LearnedBy(pub Entity);
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Mana {
+    pub current: i32,
+    pub max: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesHaste {
+    pub turns: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesSlow {
+    pub turns: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Resting;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AutoExploring {
+    // The health of the entity when exploring started
+    pub health: i32,
+    // Items that were already known about when exploring started
+    pub seen_items: HashSet<Entity>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Travelling {
+    // The remaining steps to the travel destination
+    pub path: Vec<Point>,
+    // Monsters that were already in view when travelling started
+    pub seen_monsters: HashSet<Entity>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct FieldOfView {
     pub visible_tiles: HashSet<Point>,
     pub radius: i32,
@@ -73,3 +370,244 @@ impl FieldOfView {
         }
     }
 }
+
+// The speed of an entity that acts once per turn, and the energy each action costs
+pub const NORMAL_SPEED: i32 = 100;
+pub const ACTION_COST: i32 = 100;
+
+/*
+ * Energy builds up by an entity's speed every turn and is spent on actions, so fast
+ * entities may act more than once a turn and slow entities only on some turns. Speeds are
+ * relative to the player, who acts whenever input is given, so only the speed of the
+ * player's energy is used.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Energy {
+    pub current: i32,
+    pub speed: i32,
+}
+
+impl Energy {
+    pub fn new(speed: i32) -> Self {
+        Self { current: 0, speed }
+    }
+
+    // The speed after haste, which doubles it, and slow, which halves it
+    pub fn effective_speed(&self, hasted: bool, slowed: bool) -> i32 {
+        let mut speed = self.speed;
+        if hasted {
+            speed *= 2;
+        }
+        if slowed {
+            speed /= 2;
+        }
+        speed
+    }
+
+    /*
+     * Build up a turn's worth of energy. The more time the player's action takes, the
+     * more energy is gained.
+     */
+    pub fn gain(&mut self, speed: i32, player_speed: i32) {
+        self.current += speed * NORMAL_SPEED / i32::max(1, player_speed);
+    }
+
+    pub fn can_act(&self) -> bool {
+        self.current >= ACTION_COST
+    }
+
+    // Spend the energy for an action, if there is enough
+    pub fn try_act(&mut self) -> bool {
+        if self.can_act() {
+            self.current -= ACTION_COST;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+// Marks the monsters that have spent energy to act in the current monster phase
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Acting;
+
+/*
+ * An effect that lasts for a number of turns. Timed effects wear off at the end of each
+ * player turn and are removed once they have no turns left.
+ */
+pub trait TimedEffect {
+    fn turns_left(&mut self) -> &mut i32;
+}
+
+// Doubles the entity's speed for the remaining turns
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Hasted {
+    pub turns: i32,
+}
+
+impl TimedEffect for Hasted {
+    fn turns_left(&mut self) -> &mut i32 {
+        &mut self.turns
+    }
+}
+
+// Halves the entity's speed for the remaining turns
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Slowed {
+    pub turns: i32,
+}
+
+impl TimedEffect for Slowed {
+    fn turns_left(&mut self) -> &mut i32 {
+        &mut self.turns
+    }
+}
+
+// A monster running away from the player for the remaining turns
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Afraid {
+    pub turns: i32,
+}
+
+impl TimedEffect for Afraid {
+    fn turns_left(&mut self) -> &mut i32 {
+        &mut self.turns
+    }
+}
+
+// Senses every monster on the map for the remaining turns, whether or not they are in view
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DetectingMonsters {
+    pub turns: i32,
+}
+
+impl TimedEffect for DetectingMonsters {
+    fn turns_left(&mut self) -> &mut i32 {
+        &mut self.turns
+    }
+}
+
+// A monster that is asleep. Sleeping monsters don't move until they notice the player
+// or are woken by noise.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sleeping;
+
+// How hard the entity is to notice. Sleeping monsters are less likely to notice a
+// stealthy player.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Stealth {
+    pub value: i32,
+}
+
+// A monster heading to where it heard a noise
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Investigating {
+    pub destination: Point,
+}
+
+// A message that a noise was made. It is heard by monsters within the radius, measured
+// along the paths through the map so walls muffle the noise.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Noise {
+    pub position: Point,
+    pub radius: i32,
+}
+
+/*
+ * Where the player last saw an entity. The entity is drawn dimmed at this position while
+ * it is out of view, until the player sees the tile again.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LastSeen {
+    pub position: Point,
+}
+
+// Lights the tiles it can see within its radius, fading towards the edge of the radius
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LightSource {
+    pub radius: i32,
+    pub color: RGB,
+}
+
+/*
+ * A short lived visual effect drawn on the entity layer. Particles age with the frame
+ * time rather than with turns and are removed once they have lived for their lifetime.
+ * The position is kept in the particle rather than as a Point so particles are never
+ * mistaken for entities on the map.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Particle {
+    pub position: Point,
+    pub color: ColorPair,
+    pub glyph: FontCharType,
+    // How long the particle has been shown in milliseconds
+    pub age_ms: f32,
+    pub lifetime_ms: f32,
+    // The number of tiles the particle floats upward over its lifetime
+    pub rise: i32,
+}
+
+impl Particle {
+    pub fn new(position: Point, color: ColorPair, glyph: FontCharType, lifetime_ms: f32) -> Self {
+        Self {
+            position,
+            color,
+            glyph,
+            age_ms: 0.0,
+            lifetime_ms,
+            rise: 0,
+        }
+    }
+
+    // The position the particle is drawn at once it has floated upward
+    pub fn current_position(&self) -> Point {
+        let risen = (self.rise as f32 * self.age_ms / self.lifetime_ms) as i32;
+        self.position - Point::new(0, risen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn haste_doubles_and_slow_halves_speed() {
+        let energy = Energy::new(NORMAL_SPEED);
+        assert_eq!(energy.effective_speed(false, false), NORMAL_SPEED);
+        assert_eq!(energy.effective_speed(true, false), NORMAL_SPEED * 2);
+        assert_eq!(energy.effective_speed(false, true), NORMAL_SPEED / 2);
+        assert_eq!(energy.effective_speed(true, true), NORMAL_SPEED);
+    }
+
+    #[test]
+    fn energy_gained_scales_with_the_player_speed() {
+        let mut energy = Energy::new(NORMAL_SPEED);
+        energy.gain(NORMAL_SPEED, NORMAL_SPEED);
+        assert_eq!(energy.current, NORMAL_SPEED);
+
+        // A hasted player takes half the time to act
+        energy.gain(NORMAL_SPEED, NORMAL_SPEED * 2);
+        assert_eq!(energy.current, NORMAL_SPEED + NORMAL_SPEED / 2);
+    }
+
+    #[test]
+    fn acting_spends_energy_and_keeps_the_rest() {
+        let mut energy = Energy::new(150);
+        energy.gain(150, NORMAL_SPEED);
+        assert!(energy.try_act());
+        assert_eq!(energy.current, 150 - ACTION_COST);
+        assert!(!energy.try_act());
+        assert_eq!(energy.current, 150 - ACTION_COST);
+    }
+
+    #[test]
+    fn enraged_boss_summons_twice_as_often() {
+        let mut boss = Boss {
+            enraged: false,
+            summon_cooldown: BOSS_SUMMON_INTERVAL,
+        };
+        assert_eq!(boss.summon_interval(), BOSS_SUMMON_INTERVAL);
+        boss.enraged = true;
+        assert_eq!(boss.summon_interval(), BOSS_SUMMON_INTERVAL / 2);
+    }
+}
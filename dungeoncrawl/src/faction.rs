@@ -0,0 +1,115 @@
+use crate::prelude::*;
+
+// How the members of one faction treat the members of another
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reaction {
+    Hostile,
+    Neutral,
+    Friendly,
+}
+
+impl Faction {
+    /*
+     * The reaction table. Members of a faction are friendly with each other. Merchants
+     * stay out of every fight, the undead attack all living things and every other
+     * creature in the dungeon is out to get the player and their companions. Goblins
+     * also hunt the wildlife.
+     */
+    pub fn reaction_to(&self, other: Faction) -> Reaction {
+        if *self == other {
+            return Reaction::Friendly;
+        }
+
+        match (*self, other) {
+            (Faction::Traders, _) | (_, Faction::Traders) => Reaction::Neutral,
+            (Faction::Undead, _) | (_, Faction::Undead) => Reaction::Hostile,
+            (Faction::Player, _) | (_, Faction::Player) => Reaction::Hostile,
+            (Faction::Goblins, Faction::Wildlife) => Reaction::Hostile,
+            _ => Reaction::Neutral,
+        }
+    }
+}
+
+/*
+ * How one entity treats another, based on their factions. Entities without a faction
+ * are neutral towards everyone and everyone is neutral towards them.
+ */
+pub fn reaction(ecs: &SubWorld, from: Entity, to: Entity) -> Reaction {
+    let faction = |entity: Entity| {
+        ecs.entry_ref(entity)
+            .ok()
+            .and_then(|entry| entry.get_component::<Faction>().ok().copied())
+    };
+
+    match (faction(from), faction(to)) {
+        (Some(from), Some(to)) => from.reaction_to(to),
+        _ => Reaction::Neutral,
+    }
+}
+
+/*
+ * Decide whether an entity attacks another entity standing in its way rather than
+ * interacting with it or waiting for it to move
+ */
+pub fn is_hostile(ecs: &SubWorld, attacker: Entity, target: Entity) -> bool {
+    reaction(ecs, attacker, target) == Reaction::Hostile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FACTIONS: [Faction; 5] = [
+        Faction::Player,
+        Faction::Goblins,
+        Faction::Wildlife,
+        Faction::Undead,
+        Faction::Traders,
+    ];
+
+    #[test]
+    fn factions_are_friendly_with_themselves() {
+        FACTIONS.iter().for_each(|faction| {
+            assert_eq!(faction.reaction_to(*faction), Reaction::Friendly);
+        });
+    }
+
+    #[test]
+    fn traders_stay_out_of_every_fight() {
+        FACTIONS
+            .iter()
+            .filter(|faction| **faction != Faction::Traders)
+            .for_each(|faction| {
+                assert_eq!(faction.reaction_to(Faction::Traders), Reaction::Neutral);
+                assert_eq!(Faction::Traders.reaction_to(*faction), Reaction::Neutral);
+            });
+    }
+
+    #[test]
+    fn undead_and_the_player_are_hostile_to_the_other_fighters() {
+        [Faction::Goblins, Faction::Wildlife]
+            .iter()
+            .for_each(|faction| {
+                assert_eq!(faction.reaction_to(Faction::Undead), Reaction::Hostile);
+                assert_eq!(Faction::Undead.reaction_to(*faction), Reaction::Hostile);
+                assert_eq!(faction.reaction_to(Faction::Player), Reaction::Hostile);
+                assert_eq!(Faction::Player.reaction_to(*faction), Reaction::Hostile);
+            });
+        assert_eq!(
+            Faction::Player.reaction_to(Faction::Undead),
+            Reaction::Hostile
+        );
+    }
+
+    #[test]
+    fn goblins_hunt_the_wildlife_but_not_the_other_way_around() {
+        assert_eq!(
+            Faction::Goblins.reaction_to(Faction::Wildlife),
+            Reaction::Hostile
+        );
+        assert_eq!(
+            Faction::Wildlife.reaction_to(Faction::Goblins),
+            Reaction::Neutral
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use crate::prelude::*;
+use ron::de::from_reader;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+
+const KEY_BINDINGS_FILE: &str = "resources/keybindings.ron";
+
+// The keys that can be bound in the key bindings file, which names them as they are named
+// here
+const BINDABLE_KEYS: &[VirtualKeyCode] = &[
+    VirtualKeyCode::A,
+    VirtualKeyCode::B,
+    VirtualKeyCode::C,
+    VirtualKeyCode::D,
+    VirtualKeyCode::E,
+    VirtualKeyCode::F,
+    VirtualKeyCode::G,
+    VirtualKeyCode::H,
+    VirtualKeyCode::I,
+    VirtualKeyCode::J,
+    VirtualKeyCode::K,
+    VirtualKeyCode::L,
+    VirtualKeyCode::M,
+    VirtualKeyCode::N,
+    VirtualKeyCode::O,
+    VirtualKeyCode::P,
+    VirtualKeyCode::Q,
+    VirtualKeyCode::R,
+    VirtualKeyCode::S,
+    VirtualKeyCode::T,
+    VirtualKeyCode::U,
+    VirtualKeyCode::V,
+    VirtualKeyCode::W,
+    VirtualKeyCode::X,
+    VirtualKeyCode::Y,
+    VirtualKeyCode::Z,
+    VirtualKeyCode::Key0,
+    VirtualKeyCode::Key1,
+    VirtualKeyCode::Key2,
+    VirtualKeyCode::Key3,
+    VirtualKeyCode::Key4,
+    VirtualKeyCode::Key5,
+    VirtualKeyCode::Key6,
+    VirtualKeyCode::Key7,
+    VirtualKeyCode::Key8,
+    VirtualKeyCode::Key9,
+    VirtualKeyCode::Numpad0,
+    VirtualKeyCode::Numpad1,
+    VirtualKeyCode::Numpad2,
+    VirtualKeyCode::Numpad3,
+    VirtualKeyCode::Numpad4,
+    VirtualKeyCode::Numpad5,
+    VirtualKeyCode::Numpad6,
+    VirtualKeyCode::Numpad7,
+    VirtualKeyCode::Numpad8,
+    VirtualKeyCode::Numpad9,
+    VirtualKeyCode::NumpadEnter,
+    VirtualKeyCode::F1,
+    VirtualKeyCode::F2,
+    VirtualKeyCode::F3,
+    VirtualKeyCode::F4,
+    VirtualKeyCode::F5,
+    VirtualKeyCode::F6,
+    VirtualKeyCode::F7,
+    VirtualKeyCode::F8,
+    VirtualKeyCode::F9,
+    VirtualKeyCode::F10,
+    VirtualKeyCode::F11,
+    VirtualKeyCode::F12,
+    VirtualKeyCode::Left,
+    VirtualKeyCode::Right,
+    VirtualKeyCode::Up,
+    VirtualKeyCode::Down,
+    VirtualKeyCode::Home,
+    VirtualKeyCode::End,
+    VirtualKeyCode::PageUp,
+    VirtualKeyCode::PageDown,
+    VirtualKeyCode::Insert,
+    VirtualKeyCode::Delete,
+    VirtualKeyCode::Back,
+    VirtualKeyCode::Return,
+    VirtualKeyCode::Space,
+    VirtualKeyCode::Tab,
+    VirtualKeyCode::Escape,
+    VirtualKeyCode::Comma,
+    VirtualKeyCode::Period,
+    VirtualKeyCode::Slash,
+    VirtualKeyCode::Semicolon,
+    VirtualKeyCode::Apostrophe,
+    VirtualKeyCode::LBracket,
+    VirtualKeyCode::RBracket,
+    VirtualKeyCode::Minus,
+    VirtualKeyCode::Equals,
+];
+
+// The name of a key, as used in the key bindings file and shown in the options
+pub fn key_name(key: VirtualKeyCode) -> String {
+    format!("{:?}", key)
+}
+
+// The bindable key with the given name, if there is one
+fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+    BINDABLE_KEYS
+        .iter()
+        .copied()
+        .find(|key| key_name(*key) == name)
+}
+
+// Whether the left mouse button was clicked during the current tick
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MouseClick(pub bool);
+
+// The number of milliseconds since the previous tick
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameTime(pub f32);
+
+/*
+ * The travel path previewed to the tile under the mouse. The path is kept until the player
+ * moves, the mouse moves to another tile or the map changes, rather than being planned
+ * again every tick.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TravelPreview {
+    // The start, the destination and the map revision the path was planned for
+    pub planned_for: Option<(Point, Point, u64)>,
+    pub path: Option<Vec<Point>>,
+}
+
+/*
+ * The actions the player can take from the keyboard. Keys are translated to actions once
+ * per tick so that systems read actions rather than keys and the keys can be rebound.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Wait,
+    PickUp,
+    UseItem(usize),
+    CastSpell,
+    Confirm,
+    AutoExplore,
+    Rest,
+    Look,
+    MapOverview,
+    ToggleMinimap,
+    Pause,
+}
+
+// The actions moving the player and the cursors, in the order they are named in prompts
+pub const MOVE_ACTIONS: [Action; 4] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::MoveUp,
+    Action::MoveDown,
+];
+
+impl Action {
+    /*
+     * The change in position for a movement action. Other actions don't move.
+     */
+    pub fn delta(&self) -> Point {
+        match self {
+            Action::MoveLeft => Point::new(-1, 0),
+            Action::MoveRight => Point::new(1, 0),
+            Action::MoveUp => Point::new(0, -1),
+            Action::MoveDown => Point::new(0, 1),
+            _ => Point::zero(),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Action::MoveLeft => "Move left".to_string(),
+            Action::MoveRight => "Move right".to_string(),
+            Action::MoveUp => "Move up".to_string(),
+            Action::MoveDown => "Move down".to_string(),
+            Action::Wait => "Wait".to_string(),
+            Action::PickUp => "Pick up".to_string(),
+            Action::UseItem(slot) => format!("Use item {}", slot + 1),
+            Action::CastSpell => "Cast spell".to_string(),
+            Action::Confirm => "Confirm".to_string(),
+            Action::AutoExplore => "Auto explore".to_string(),
+            Action::Rest => "Rest".to_string(),
+            Action::Look => "Look".to_string(),
+            Action::MapOverview => "Map overview".to_string(),
+            Action::ToggleMinimap => "Toggle minimap".to_string(),
+            Action::Pause => "Pause".to_string(),
+        }
+    }
+}
+
+/*
+ * Maps keys to actions. The bindings are loaded from the key bindings file, falling back
+ * to the default bindings if the file is missing or can't be read. Keys bound in the key
+ * map take priority over the movement keys chosen in the options.
+ */
+#[derive(Clone, Debug)]
+pub struct KeyMap {
+    pub bindings: HashMap<VirtualKeyCode, Action>,
+}
+
+/*
+ * The key bindings as they are written in the key bindings file, with each key given by
+ * its name. Keys are read by name so the file format doesn't depend on how the windowing
+ * library serializes its key codes.
+ */
+#[derive(Deserialize)]
+#[serde(rename = "KeyMap")]
+struct KeyBindingsFile {
+    bindings: HashMap<String, Action>,
+}
+
+impl KeyMap {
+    pub fn new() -> Self {
+        let mut bindings = HashMap::new();
+        bindings.insert(VirtualKeyCode::Left, Action::MoveLeft);
+        bindings.insert(VirtualKeyCode::Right, Action::MoveRight);
+        bindings.insert(VirtualKeyCode::Up, Action::MoveUp);
+        bindings.insert(VirtualKeyCode::Down, Action::MoveDown);
+        bindings.insert(VirtualKeyCode::Space, Action::Wait);
+        bindings.insert(VirtualKeyCode::Numpad5, Action::Wait);
+        bindings.insert(VirtualKeyCode::G, Action::PickUp);
+        bindings.insert(VirtualKeyCode::Key1, Action::UseItem(0));
+        bindings.insert(VirtualKeyCode::Key2, Action::UseItem(1));
+        bindings.insert(VirtualKeyCode::Key3, Action::UseItem(2));
+        bindings.insert(VirtualKeyCode::Key4, Action::UseItem(3));
+        bindings.insert(VirtualKeyCode::Key5, Action::UseItem(4));
+        bindings.insert(VirtualKeyCode::Key6, Action::UseItem(5));
+        bindings.insert(VirtualKeyCode::Key7, Action::UseItem(6));
+        bindings.insert(VirtualKeyCode::Key8, Action::UseItem(7));
+        bindings.insert(VirtualKeyCode::Key9, Action::UseItem(8));
+        bindings.insert(VirtualKeyCode::C, Action::CastSpell);
+        bindings.insert(VirtualKeyCode::Return, Action::Confirm);
+        bindings.insert(VirtualKeyCode::X, Action::AutoExplore);
+        bindings.insert(VirtualKeyCode::R, Action::Rest);
+        bindings.insert(VirtualKeyCode::L, Action::Look);
+        bindings.insert(VirtualKeyCode::Tab, Action::MapOverview);
+        bindings.insert(VirtualKeyCode::M, Action::ToggleMinimap);
+        bindings.insert(VirtualKeyCode::Escape, Action::Pause);
+        Self { bindings }
+    }
+
+    pub fn load() -> Self {
+        let file = match File::open(KEY_BINDINGS_FILE) {
+            Ok(file) => file,
+            Err(_) => return Self::new(),
+        };
+        let file: KeyBindingsFile = match from_reader(file) {
+            Ok(file) => file,
+            Err(error) => {
+                log(format!(
+                    "unable to load key bindings, using defaults: {}",
+                    error
+                ));
+                return Self::new();
+            }
+        };
+
+        // Skip any key that isn't known rather than failing the whole file
+        let bindings = file
+            .bindings
+            .into_iter()
+            .filter_map(|(name, action)| match key_from_name(&name) {
+                Some(key) => Some((key, action)),
+                None => {
+                    log(format!("unknown key in key bindings: {}", name));
+                    None
+                }
+            })
+            .collect();
+        Self { bindings }
+    }
+
+    // Get the action for the given key, if any
+    pub fn action(&self, key: VirtualKeyCode, movement_keys: MovementKeys) -> Option<Action> {
+        self.bindings
+            .get(&key)
+            .copied()
+            .or_else(|| movement_keys.action(key))
+    }
+
+    // Get the names of every key that performs the given action
+    pub fn keys_for(&self, action: Action, movement_keys: MovementKeys) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(key, _)| key_name(*key))
+            .collect();
+        keys.sort();
+        movement_keys
+            .bindings()
+            .iter()
+            .filter(|(key, bound)| *bound == action && !self.bindings.contains_key(key))
+            .for_each(|(key, _)| keys.push(key_name(*key)));
+        keys
+    }
+
+    // The names of the keys for any of the actions, for prompts such as "Escape or L"
+    pub fn prompt(&self, actions: &[Action], movement_keys: MovementKeys) -> String {
+        actions
+            .iter()
+            .flat_map(|action| self.keys_for(*action, movement_keys))
+            .collect::<Vec<String>>()
+            .join(" or ")
+    }
+
+    /*
+     * The first key bound to each of the actions, for prompts such as "Left/Right/Up/Down".
+     * Only one key per action is named to keep the prompt short.
+     */
+    pub fn short_prompt(&self, actions: &[Action], movement_keys: MovementKeys) -> String {
+        actions
+            .iter()
+            .filter_map(|action| self.keys_for(*action, movement_keys).into_iter().next())
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+}
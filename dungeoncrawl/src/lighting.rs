@@ -0,0 +1,88 @@
+use crate::prelude::*;
+
+// The share of a glyphs color that is always shown on a lit tile, however dim the light
+const MIN_BRIGHTNESS: f32 = 0.25;
+
+/*
+ * The light falling on every tile of the map. Light levels are colors with each channel
+ * between 0 and 1, where a tile with no light at all is unlit. Lighting is rebuilt from
+ * the light sources whenever they move or change, or the map changes shape, and is not
+ * saved.
+ */
+pub struct Lighting {
+    width: i32,
+    height: i32,
+    levels: Vec<RGB>,
+    // The light sources and their positions the light was last built from
+    sources: Vec<(Point, LightSource)>,
+    // Whether the light must be rebuilt even if the light sources are unchanged
+    dirty: bool,
+}
+
+impl Lighting {
+    pub fn new(map: &Map) -> Self {
+        Self {
+            width: map.width,
+            height: map.height,
+            levels: vec![RGB::new(); (map.width * map.height) as usize],
+            sources: Vec::new(),
+            dirty: true,
+        }
+    }
+
+    fn try_idx(&self, point: Point) -> Option<usize> {
+        if point.x < 0 || point.x >= self.width || point.y < 0 || point.y >= self.height {
+            return None;
+        }
+
+        Some((point.y * self.width + point.x) as usize)
+    }
+
+    // Rebuild the light the next time it is checked, such as after walls were dug through
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    // Whether the light was built from exactly these light sources and is still current
+    pub fn is_current(&self, sources: &[(Point, LightSource)]) -> bool {
+        !self.dirty && self.sources == sources
+    }
+
+    // Remove all light from the map, ready to be lit again by the given light sources
+    pub fn reset(&mut self, sources: Vec<(Point, LightSource)>) {
+        self.levels.iter_mut().for_each(|level| *level = RGB::new());
+        self.sources = sources;
+        self.dirty = false;
+    }
+
+    /*
+     * Add light to a tile. The light from every source reaching a tile is added together
+     * and capped at full brightness.
+     */
+    pub fn add_light(&mut self, point: Point, light: RGB) {
+        if let Some(idx) = self.try_idx(point) {
+            let level = self.levels[idx] + light;
+            self.levels[idx] = RGB::from_f32(
+                f32::min(level.r, 1.0),
+                f32::min(level.g, 1.0),
+                f32::min(level.b, 1.0),
+            );
+        }
+    }
+
+    pub fn light_at(&self, point: Point) -> RGB {
+        self.try_idx(point)
+            .map_or(RGB::new(), |idx| self.levels[idx])
+    }
+
+    pub fn is_lit(&self, point: Point) -> bool {
+        let level = self.light_at(point);
+        level.r > 0.0 || level.g > 0.0 || level.b > 0.0
+    }
+
+    // Shade a color by the light falling on the given tile
+    pub fn tint(&self, point: Point, color: RGBA) -> RGBA {
+        let level = self.light_at(point) * (1.0 - MIN_BRIGHTNESS) + MIN_BRIGHTNESS;
+        color * level.to_rgba(1.0)
+    }
+}
@@ -1,7 +1,13 @@
 mod camera;
 mod components;
+mod faction;
+mod input;
+mod lighting;
 mod map;
 mod map_builder;
+mod menu;
+mod options;
+mod save;
 mod spawners;
 mod systems;
 mod turn_state;
@@ -15,10 +21,20 @@ mod prelude {
     pub const SCREEN_HEIGHT: i32 = 50;
     pub const DISPLAY_WIDTH: i32 = SCREEN_WIDTH / 2;
     pub const DISPLAY_HEIGHT: i32 = SCREEN_HEIGHT / 2;
+    // The size of new dungeon levels. Maps carry their own size so it may differ from
+    // the size of the screen.
+    pub const MAP_WIDTH: i32 = 80;
+    pub const MAP_HEIGHT: i32 = 50;
     pub use crate::camera::*;
     pub use crate::components::*;
+    pub use crate::faction::*;
+    pub use crate::input::*;
+    pub use crate::lighting::*;
     pub use crate::map::*;
     pub use crate::map_builder::*;
+    pub use crate::menu::*;
+    pub use crate::options::*;
+    pub use crate::save::*;
     pub use crate::spawners::*;
     pub use crate::systems::*;
     pub use crate::turn_state::*;
@@ -26,43 +42,46 @@ mod prelude {
 
 use prelude::*;
 
+// The number of items a merchant has for sale
+const SHOP_STOCK: usize = 6;
+
 struct State {
     ecs: World,
     resources: Resources,
     input_systems: Schedule,
     player_systems: Schedule,
     monster_systems: Schedule,
+    look_systems: Schedule,
+    spell_menu_systems: Schedule,
+    targeting_systems: Schedule,
+    shop_systems: Schedule,
+    map_overview_systems: Schedule,
+    particle_systems: Schedule,
+    menu: Menu,
+    options: Options,
+    key_map: KeyMap,
 }
 
 impl State {
     fn new() -> Self {
-        let mut ecs = World::default();
         let mut resources = Resources::default();
-        let mut rng = RandomNumberGenerator::new();
-        let map_builder = MapBuilder::new(&mut rng);
-        // Spawn the player within the rendered map
-        spawn_player(&mut ecs, map_builder.player_start);
-        // Spawn the Amulet of Yala
-        spawn_amulet_of_yala(&mut ecs, map_builder.amulet_start);
-        // Spawn an enemy in each room other then the first room
-        map_builder
-            .rooms
-            .iter()
-            .skip(1)
-            .map(|room| room.center())
-            .for_each(|position| spawn_monster(&mut ecs, &mut rng, position));
-        // Add the map as a resource
-        resources.insert(map_builder.map);
-        // Add the camera as a resource
-        resources.insert(Camera::new(map_builder.player_start));
-        // Set the default state the waiting input
-        resources.insert(TurnState::AwaitingInput);
+        // Start at the main menu. A game is created once one is started or continued.
+        resources.insert(TurnState::MainMenu);
         Self {
-            ecs,
+            ecs: World::default(),
             resources,
             input_systems: build_input_scheduler(),
             player_systems: build_player_scheduler(),
             monster_systems: build_monster_scheduler(),
+            look_systems: build_look_scheduler(),
+            spell_menu_systems: build_spell_menu_scheduler(),
+            targeting_systems: build_targeting_scheduler(),
+            shop_systems: build_shop_scheduler(),
+            map_overview_systems: build_map_overview_scheduler(),
+            particle_systems: build_particle_scheduler(),
+            menu: Menu::new(),
+            options: Options::new(),
+            key_map: KeyMap::load(),
         }
     }
 
@@ -70,24 +89,65 @@ impl State {
         self.ecs = World::default();
         self.resources = Resources::default();
         let mut rng = RandomNumberGenerator::new();
-        let map_builder = MapBuilder::new(&mut rng);
+        let map_builder = MapBuilder::new(&mut rng, MAP_WIDTH, MAP_HEIGHT);
         // Spawn the player within the rendered map
-        spawn_player(&mut self.ecs, map_builder.player_start);
+        let player = spawn_player(&mut self.ecs, map_builder.player_start);
+        // Spawn the player's companion beside them
+        if let Some(position) = map_builder.companion_start {
+            spawn_companion(&mut self.ecs, position);
+        }
         // Spawn the Amulet of Yala
         spawn_amulet_of_yala(&mut self.ecs, map_builder.amulet_start);
-        // Spawn an enemy in each room other then the first room
+        // Spawn the boss guarding the amulet
+        spawn_boss(
+            &mut self.ecs,
+            map_builder.boss_start.unwrap_or(map_builder.amulet_start),
+        );
+        // Spawn an enemy in each room other then the first room, the shop and the room
+        // the boss guards
         map_builder
             .rooms
             .iter()
             .skip(1)
+            .filter(|room| map_builder.shop != Some(**room))
+            .filter(|room| !room.point_in_rect(map_builder.amulet_start))
             .map(|room| room.center())
             .for_each(|position| spawn_monster(&mut self.ecs, &mut rng, position));
+        // Spawn the torches lighting the rooms
+        map_builder
+            .torch_spawns
+            .iter()
+            .for_each(|position| spawn_torch(&mut self.ecs, *position));
+        // Spawn items and spells from the templates
+        let templates = Templates::load();
+        let appearances = templates.assign_appearances(&mut rng);
+        templates.spawn_entities(
+            &mut self.ecs,
+            &mut rng,
+            &map_builder.item_spawns,
+            &appearances,
+        );
+        templates.spawn_spells(&mut self.ecs, player);
+        // Open the shop with a merchant and their stock
+        if let Some(shop) = map_builder.shop {
+            let merchant = spawn_merchant(&mut self.ecs, shop.center());
+            templates.spawn_shop_stock(&mut self.ecs, &mut rng, merchant, SHOP_STOCK, &appearances);
+        }
+        // Add the camera as a resource
+        self.resources.insert(Camera::new(
+            map_builder.player_start,
+            self.options.camera_mode,
+            &map_builder.map,
+        ));
+        // Add the lighting as a resource, it is filled in by the lighting system
+        self.resources.insert(Lighting::new(&map_builder.map));
+        self.resources.insert(TravelPreview::default());
+        // Add the key bindings as a resource so prompts can name the bound keys
+        self.resources.insert(self.key_map.clone());
         // Add the map as a resource
         self.resources.insert(map_builder.map);
-        // Add the camera as a resource
-        self.resources.insert(Camera::new(map_builder.player_start));
         // Set the default state the waiting input
-        self.resources.insert(TurnState::AwaitingInput);
+        self.set_turn_state(TurnState::AwaitingInput);
     }
 
     fn game_over(&mut self, ctx: &mut BTerm) {
@@ -111,10 +171,23 @@ impl State {
             BLACK,
             "Don't worry, you can always try again with a new hero.",
         );
-        ctx.print_color_centered(9, GREEN, BLACK, "Press 1 to play again.");
+        ctx.print_color_centered(
+            9,
+            GREEN,
+            BLACK,
+            format!("Press {} to play again.", self.keys_for(Action::Confirm)),
+        );
+        ctx.print_color_centered(
+            10,
+            GREEN,
+            BLACK,
+            format!("Press {} for the main menu.", self.keys_for(Action::Pause)),
+        );
 
-        if let Some(VirtualKeyCode::Key1) = ctx.key {
-            self.reset_game_state();
+        match self.action() {
+            Some(Action::Confirm) => self.reset_game_state(),
+            Some(Action::Pause) => self.set_turn_state(TurnState::MainMenu),
+            _ => {}
         }
     }
 
@@ -125,19 +198,223 @@ impl State {
             4,
             WHITE,
             BLACK,
-            "You put on the Amulet of Yala and feel its power course through your veins.",
+            "The Goblin King lies slain, its horde scattered through the dungeon.",
         );
         ctx.print_color_centered(
             5,
             WHITE,
             BLACK,
+            "You put on the Amulet of Yala and feel its power course through your veins.",
+        );
+        ctx.print_color_centered(
+            6,
+            WHITE,
+            BLACK,
             "Your town is saved, and you can return to your normal life",
         );
-        ctx.print_color_centered(7, GREEN, BLACK, "Press 1 to play again.");
-        if let Some(VirtualKeyCode::Key1) = ctx.key {
-            self.reset_game_state();
+        ctx.print_color_centered(
+            8,
+            GREEN,
+            BLACK,
+            format!("Press {} to play again.", self.keys_for(Action::Confirm)),
+        );
+        ctx.print_color_centered(
+            9,
+            GREEN,
+            BLACK,
+            format!("Press {} for the main menu.", self.keys_for(Action::Pause)),
+        );
+        match self.action() {
+            Some(Action::Confirm) => self.reset_game_state(),
+            Some(Action::Pause) => self.set_turn_state(TurnState::MainMenu),
+            _ => {}
         }
     }
+
+    // The action for the key pressed this tick, which the menus share with the systems
+    fn action(&self) -> Option<Action> {
+        self.resources
+            .get::<Option<Action>>()
+            .and_then(|action| *action)
+    }
+
+    // The names of the keys bound to the action, for the prompts outside of the game
+    fn keys_for(&self, action: Action) -> String {
+        self.key_map.prompt(&[action], self.options.movement_keys)
+    }
+
+    /*
+     * Change the turn state from outside of the systems. The menu selection is reset so
+     * each menu starts at its first choice.
+     */
+    fn set_turn_state(&mut self, turn_state: TurnState) {
+        self.menu.selection = 0;
+        self.resources.insert(turn_state);
+    }
+
+    /*
+     * Replace the current game with the saved game. The camera is rebuilt around the
+     * player as it is not saved.
+     */
+    fn continue_game(&mut self) {
+        match load_game() {
+            Ok((ecs, map)) => {
+                let player_position = *<&Point>::query()
+                    .filter(component::<Player>())
+                    .iter(&ecs)
+                    .next()
+                    .unwrap();
+                self.ecs = ecs;
+                self.resources = Resources::default();
+                self.resources
+                    .insert(Camera::new(player_position, self.options.camera_mode, &map));
+                self.resources.insert(Lighting::new(&map));
+                self.resources.insert(TravelPreview::default());
+                self.resources.insert(self.key_map.clone());
+                self.resources.insert(map);
+                self.set_turn_state(TurnState::AwaitingInput);
+            }
+            Err(error) => log(format!("unable to load saved game: {}", error)),
+        }
+    }
+
+    fn main_menu(&mut self, ctx: &mut BTerm) {
+        ctx.set_active_console(2);
+        ctx.print_color_centered(10, YELLOW, BLACK, "Dungeon Crawler");
+        ctx.print_color_centered(
+            12,
+            WHITE,
+            BLACK,
+            "Find the Amulet of Yala and save your home town.",
+        );
+
+        let mut choices = vec![(MenuChoice::NewGame, "New Game".to_string())];
+        if has_save_game() {
+            choices.push((MenuChoice::Continue, "Continue".to_string()));
+        }
+        choices.push((MenuChoice::Options, "Options".to_string()));
+        choices.push((MenuChoice::Quit, "Quit".to_string()));
+
+        match self.menu.show(ctx, 18, &choices, self.action()) {
+            Some(MenuChoice::NewGame) => self.reset_game_state(),
+            Some(MenuChoice::Continue) => self.continue_game(),
+            Some(MenuChoice::Options) => self.set_turn_state(TurnState::Options { paused: false }),
+            Some(MenuChoice::Quit) => ctx.quitting = true,
+            _ => {}
+        }
+    }
+
+    fn pause_menu(&mut self, ctx: &mut BTerm) {
+        ctx.set_active_console(2);
+        ctx.print_color_centered(10, YELLOW, BLACK, "Paused");
+
+        let choices = [
+            (MenuChoice::Resume, "Resume".to_string()),
+            (MenuChoice::Options, "Options".to_string()),
+            (MenuChoice::SaveAndQuit, "Save and Quit".to_string()),
+        ];
+
+        // Pausing again resumes the game as well as the resume choice
+        if let Some(Action::Pause) = self.action() {
+            self.set_turn_state(TurnState::AwaitingInput);
+            return;
+        }
+
+        match self.menu.show(ctx, 14, &choices, self.action()) {
+            Some(MenuChoice::Resume) => self.set_turn_state(TurnState::AwaitingInput),
+            Some(MenuChoice::Options) => self.set_turn_state(TurnState::Options { paused: true }),
+            Some(MenuChoice::SaveAndQuit) => {
+                let map = self.resources.get::<Map>().unwrap();
+                if let Err(error) = save_game(&self.ecs, &map) {
+                    log(format!("unable to save game: {}", error));
+                    return;
+                }
+                drop(map);
+                self.set_turn_state(TurnState::MainMenu);
+            }
+            _ => {}
+        }
+    }
+
+    /*
+     * Show the options screen. Options are changed by picking them and the screen returns
+     * to the menu it was opened from.
+     */
+    fn options_menu(&mut self, ctx: &mut BTerm, paused: bool) {
+        ctx.set_active_console(2);
+        ctx.print_color_centered(10, YELLOW, BLACK, "Options");
+
+        let choices = [
+            (
+                MenuChoice::MovementKeys,
+                format!("Movement keys: {}", self.options.movement_keys.name()),
+            ),
+            (
+                MenuChoice::Colors,
+                format!("Colors: {}", self.options.color_scheme.name()),
+            ),
+            (
+                MenuChoice::Camera,
+                format!("Camera: {}", self.options.camera_mode.name()),
+            ),
+            (MenuChoice::Back, "Back".to_string()),
+        ];
+
+        let previous_state = if paused {
+            TurnState::Paused
+        } else {
+            TurnState::MainMenu
+        };
+        if let Some(Action::Pause) = self.action() {
+            self.set_turn_state(previous_state);
+            return;
+        }
+
+        match self.menu.show(ctx, 14, &choices, self.action()) {
+            Some(MenuChoice::MovementKeys) => {
+                self.options.movement_keys = self.options.movement_keys.next()
+            }
+            Some(MenuChoice::Colors) => {
+                self.options.color_scheme = self.options.color_scheme.next()
+            }
+            Some(MenuChoice::Camera) => self.options.camera_mode = self.options.camera_mode.next(),
+            Some(MenuChoice::Back) => self.set_turn_state(previous_state),
+            _ => {}
+        }
+
+        // List the key bindings for reference
+        let mut actions = vec![
+            Action::MoveLeft,
+            Action::MoveRight,
+            Action::MoveUp,
+            Action::MoveDown,
+            Action::Wait,
+            Action::PickUp,
+        ];
+        actions.extend((0..9).map(Action::UseItem));
+        actions.extend(vec![
+            Action::CastSpell,
+            Action::Confirm,
+            Action::AutoExplore,
+            Action::Rest,
+            Action::Look,
+            Action::MapOverview,
+            Action::ToggleMinimap,
+            Action::Pause,
+        ]);
+        let mut bindings: Vec<String> = actions
+            .iter()
+            .map(|action| {
+                let keys = self.key_map.keys_for(*action, self.options.movement_keys);
+                format!("{}: {}", action.name(), keys.join(", "))
+            })
+            .collect();
+        bindings.push("Travel: Left click".to_string());
+        ctx.print_color_centered(22, YELLOW, BLACK, "Key Bindings");
+        bindings.iter().enumerate().for_each(|(i, binding)| {
+            ctx.print_color_centered(24 + i as i32, WHITE, BLACK, binding);
+        });
+    }
 }
 
 impl GameState for State {
@@ -151,17 +428,35 @@ impl GameState for State {
         // Clear HUD Layer
         ctx.set_active_console(2);
         ctx.cls();
-        // Add the keyboard state as a resource. This makes the keyboard state
-        // available to any system. This replaces the previous keyboard state
-        // from the last tick.
-        self.resources.insert(ctx.key);
+        // Add the action for the key pressed as a resource. This makes the
+        // keyboard state available to any system. This replaces the previous
+        // action from the last tick.
+        let action = ctx
+            .key
+            .and_then(|key| self.key_map.action(key, self.options.movement_keys));
+        // Toggling the minimap is handled here as it doesn't affect the game. Systems
+        // don't see the action so it doesn't interrupt the player or use a turn.
+        let action = match action {
+            Some(Action::ToggleMinimap) => {
+                self.options.show_minimap = !self.options.show_minimap;
+                None
+            }
+            action => action,
+        };
+        self.resources.insert(action);
         // Add the current moust position as a resource.
         // Set the active console to the base layer so coordinates line up with the appropriate
         // layer.
         ctx.set_active_console(0);
         self.resources.insert(Point::from_tuple(ctx.mouse_pos()));
+        // Add whether the mouse was clicked as a resource.
+        self.resources.insert(MouseClick(ctx.left_click));
+        // Add the player chosen options as a resource.
+        self.resources.insert(self.options);
+        // Add the time since the last tick as a resource so effects can be animated.
+        self.resources.insert(FrameTime(ctx.frame_time_ms));
         // Execute the systems for the current state
-        let current_state = self.resources.get::<TurnState>().unwrap().clone();
+        let current_state = *self.resources.get::<TurnState>().unwrap();
         match current_state {
             TurnState::AwaitingInput => self
                 .input_systems
@@ -172,9 +467,39 @@ impl GameState for State {
             TurnState::MonsterTurn => self
                 .monster_systems
                 .execute(&mut self.ecs, &mut self.resources),
+            TurnState::Looking(_) => self
+                .look_systems
+                .execute(&mut self.ecs, &mut self.resources),
+            TurnState::SpellMenu { .. } => self
+                .spell_menu_systems
+                .execute(&mut self.ecs, &mut self.resources),
+            TurnState::Targeting { .. } => self
+                .targeting_systems
+                .execute(&mut self.ecs, &mut self.resources),
+            TurnState::Shop { .. } => self
+                .shop_systems
+                .execute(&mut self.ecs, &mut self.resources),
+            TurnState::MapOverview => self
+                .map_overview_systems
+                .execute(&mut self.ecs, &mut self.resources),
+            TurnState::MainMenu => self.main_menu(ctx),
+            TurnState::Paused => self.pause_menu(ctx),
+            TurnState::Options { paused } => self.options_menu(ctx, paused),
             TurnState::GameOver => self.game_over(ctx),
             TurnState::Victory => self.victory(ctx),
         }
+        // Particles are animated by time rather than turns whenever the map is shown
+        if let TurnState::AwaitingInput
+        | TurnState::PlayerTurn
+        | TurnState::MonsterTurn
+        | TurnState::Looking(_)
+        | TurnState::SpellMenu { .. }
+        | TurnState::Targeting { .. }
+        | TurnState::Shop { .. } = current_state
+        {
+            self.particle_systems
+                .execute(&mut self.ecs, &mut self.resources);
+        }
         // TODO: Render Draw Buffer
         render_draw_buffer(ctx).expect("Render error");
     }
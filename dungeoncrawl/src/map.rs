@@ -1,14 +1,18 @@
 use crate::prelude::*;
+use serde::{Deserialize, Serialize};
 
-const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
-
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TileType {
     Wall,
     Floor,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Map {
+    // The number of tiles across and down the map
+    pub width: i32,
+    pub height: i32,
+
     /*
     Represent the entire 2 demensional map as a single dimensional vector. Map
     locations of (x, y) will be transformed into vector indices. This is known as "striding".
@@ -22,12 +26,12 @@ pub struct Map {
 
     The index of a tile given it's coordinates is calculated as follows:
 
-    let index = (y * WIDTH) + x;
+    let index = (y * width) + x;
 
     You can calculate the reciprocal - the x and y coordinates represented by an index - with:
 
-    let y = index % WIDTH;
-    let x = index / WIDTH; <-- Always rounds *DOWN*
+    let x = index % width;
+    let y = index / width; <-- Always rounds *DOWN*
     */
     pub tiles: Vec<TileType>,
 
@@ -36,25 +40,48 @@ pub struct Map {
      * for the map.
      */
     pub revealed_tiles: Vec<bool>,
-}
 
-pub fn map_idx(x: i32, y: i32) -> usize {
-    ((y * SCREEN_WIDTH) + x) as usize
+    /*
+     * The tiles changed since the systems depending on the shape of the map were last
+     * told about it. Only tiles changed through set_tile are recorded.
+     */
+    #[serde(skip)]
+    changed_tiles: Vec<Point>,
+
+    /*
+     * Counts the changes to the tiles and to the revealed tiles, so that anything worked
+     * out from the map can tell whether it needs working out again.
+     */
+    #[serde(skip)]
+    revision: u64,
 }
 
 impl Map {
-    pub fn new() -> Self {
+    pub fn new(width: i32, height: i32) -> Self {
+        let num_tiles = (width * height) as usize;
         Self {
-            tiles: vec![TileType::Floor; NUM_TILES],
-            revealed_tiles: vec![false; NUM_TILES],
+            width,
+            height,
+            tiles: vec![TileType::Floor; num_tiles],
+            revealed_tiles: vec![false; num_tiles],
+            changed_tiles: Vec::new(),
+            revision: 0,
         }
     }
 
+    /*
+     * The index in the map vectors of the given coordinates. The coordinates must be
+     * within the map, use try_idx if they may not be.
+     */
+    pub fn idx(&self, x: i32, y: i32) -> usize {
+        ((y * self.width) + x) as usize
+    }
+
     /*
      * Validate that the given point is within the bounds of the map
      */
     pub fn in_bounds(&self, point: Point) -> bool {
-        point.x >= 0 && point.x < SCREEN_WIDTH && point.y >= 0 && point.y < SCREEN_HEIGHT
+        point.x >= 0 && point.x < self.width && point.y >= 0 && point.y < self.height
     }
 
     /*
@@ -62,7 +89,53 @@ impl Map {
      * desired tile is within bounds and is a tile type that can be entered.
      */
     pub fn can_enter_tile(&self, point: Point) -> bool {
-        self.in_bounds(point) && self.tiles[map_idx(point.x, point.y)] == TileType::Floor
+        self.in_bounds(point) && self.tiles[self.idx(point.x, point.y)] == TileType::Floor
+    }
+
+    /*
+     * Validate that the given tile can be dug through. The walls on the edge of the map
+     * are too hard to dig, keeping everything within the map.
+     */
+    pub fn can_dig(&self, point: Point) -> bool {
+        point.x > 0
+            && point.x < self.width - 1
+            && point.y > 0
+            && point.y < self.height - 1
+            && self.tiles[self.idx(point.x, point.y)] == TileType::Wall
+    }
+
+    /*
+     * Change the tile at the given point once the map is built, recording the change so
+     * the fields of view and paths through it can be updated.
+     */
+    pub fn set_tile(&mut self, point: Point, tile: TileType) {
+        if let Some(idx) = self.try_idx(point) {
+            self.tiles[idx] = tile;
+            self.changed_tiles.push(point);
+            self.revision += 1;
+        }
+    }
+
+    /*
+     * Mark the tile at the index as revealed to the player
+     */
+    pub fn reveal(&mut self, idx: usize) {
+        if !self.revealed_tiles[idx] {
+            self.revealed_tiles[idx] = true;
+            self.revision += 1;
+        }
+    }
+
+    // The number of changes made to the tiles and revealed tiles so far
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /*
+     * Take the tiles changed since this was last called
+     */
+    pub fn take_changed_tiles(&mut self) -> Vec<Point> {
+        std::mem::take(&mut self.changed_tiles)
     }
 
     /*
@@ -74,7 +147,78 @@ impl Map {
             return None;
         }
 
-        Some(map_idx(point.x, point.y))
+        Some(self.idx(point.x, point.y))
+    }
+
+    /*
+     * Find the shortest path between two points on the map. Returns the list of points
+     * to walk through, excluding the start, or none if the end can't be reached.
+     */
+    pub fn path_between(&self, start: Point, end: Point) -> Option<Vec<Point>> {
+        self.find_path(start, end, self)
+    }
+
+    /*
+     * Find the shortest path between two points through the tiles revealed to the player,
+     * so that planning a route doesn't give away the parts of the map not yet explored.
+     */
+    pub fn known_path_between(&self, start: Point, end: Point) -> Option<Vec<Point>> {
+        self.find_path(start, end, &RevealedMap { map: self })
+    }
+
+    // Find the shortest path between two points using the exits of the given map
+    fn find_path(&self, start: Point, end: Point, paths: &dyn BaseMap) -> Option<Vec<Point>> {
+        if !self.can_enter_tile(start) || !self.can_enter_tile(end) || start == end {
+            return None;
+        }
+
+        let path = a_star_search(
+            self.point2d_to_index(start),
+            self.point2d_to_index(end),
+            paths,
+        );
+        if path.success {
+            Some(
+                path.steps
+                    .iter()
+                    .skip(1)
+                    .map(|idx| self.index_to_point2d(*idx))
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+
+    /*
+     * The tiles within the radius of the center that can be seen from it. Walls block
+     * the tiles behind them.
+     */
+    pub fn tiles_in_radius(&self, center: Point, radius: i32) -> Vec<Point> {
+        field_of_view(center, radius, self)
+            .into_iter()
+            .filter(|point| self.in_bounds(*point))
+            .collect()
+    }
+
+    /*
+     * The tiles on the line from the start to the end, excluding the start. The line
+     * stops at the first tile that can't be seen through.
+     */
+    pub fn line_between(&self, start: Point, end: Point) -> Vec<Point> {
+        let mut tiles = Vec::new();
+        for point in line2d(LineAlg::Bresenham, start, end).into_iter().skip(1) {
+            match self.try_idx(point) {
+                Some(idx) => {
+                    tiles.push(point);
+                    if self.is_opaque(idx) {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        tiles
     }
 
     /*
@@ -98,7 +242,7 @@ impl Map {
 
 impl Algorithm2D for Map {
     fn dimensions(&self) -> Point {
-        Point::new(SCREEN_WIDTH, SCREEN_HEIGHT)
+        Point::new(self.width, self.height)
     }
 
     fn in_bounds(&self, point: Point) -> bool {
@@ -132,6 +276,132 @@ impl BaseMap for Map {
     }
 
     fn is_opaque(&self, idx: usize) -> bool {
-        self.tiles[idx as usize] != TileType::Floor
+        self.tiles[idx] != TileType::Floor
+    }
+}
+
+/*
+ * The map as known to the player. Tiles that haven't been revealed are treated as blocked,
+ * even if they are floor.
+ */
+struct RevealedMap<'a> {
+    map: &'a Map,
+}
+
+impl BaseMap for RevealedMap<'_> {
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        self.map
+            .get_available_exits(idx)
+            .into_iter()
+            .filter(|(exit, _)| self.map.revealed_tiles[*exit])
+            .collect()
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.map.get_pathing_distance(idx1, idx2)
+    }
+
+    fn is_opaque(&self, idx: usize) -> bool {
+        self.map.is_opaque(idx)
+    }
+}
+
+/*
+ * The map as seen by a creature that tunnels. Walls that can be dug through are exits like
+ * the floor, costing the turns it takes to dig through them on top of the step itself.
+ */
+pub struct TunnelMap<'a> {
+    map: &'a Map,
+    dig_turns: i32,
+}
+
+impl<'a> TunnelMap<'a> {
+    pub fn new(map: &'a Map, dig_turns: i32) -> Self {
+        Self { map, dig_turns }
+    }
+}
+
+impl BaseMap for TunnelMap<'_> {
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        let mut exits = SmallVec::new();
+        let location = self.map.index_to_point2d(idx);
+
+        [
+            Point::new(-1, 0),
+            Point::new(1, 0),
+            Point::new(0, -1),
+            Point::new(0, 1),
+        ]
+        .iter()
+        .map(|delta| location + *delta)
+        .for_each(|destination| {
+            if self.map.can_enter_tile(destination) {
+                exits.push((self.map.point2d_to_index(destination), 1.0));
+            } else if self.map.can_dig(destination) {
+                exits.push((
+                    self.map.point2d_to_index(destination),
+                    1.0 + self.dig_turns as f32,
+                ));
+            }
+        });
+
+        exits
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.map.get_pathing_distance(idx1, idx2)
+    }
+
+    fn is_opaque(&self, idx: usize) -> bool {
+        self.map.is_opaque(idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A map bordered by walls, with a wall part way across it leaving a gap at the bottom
+    fn walled_map() -> Map {
+        let mut map = Map::new(12, 12);
+        (0..map.width).for_each(|x| map.set_tile(Point::new(x, 0), TileType::Wall));
+        (0..map.width).for_each(|x| map.set_tile(Point::new(x, 11), TileType::Wall));
+        (0..map.height).for_each(|y| map.set_tile(Point::new(0, y), TileType::Wall));
+        (0..map.height).for_each(|y| map.set_tile(Point::new(11, y), TileType::Wall));
+        (1..9).for_each(|y| map.set_tile(Point::new(5, y), TileType::Wall));
+        map
+    }
+
+    #[test]
+    fn digging_costs_the_dig_turns_on_top_of_the_step() {
+        let map = walled_map();
+        let tunnels = TunnelMap::new(&map, 3);
+        let exits = tunnels.get_available_exits(map.point2d_to_index(Point::new(4, 1)));
+        let cost_to = |point: Point| {
+            exits
+                .iter()
+                .find(|(idx, _)| *idx == map.point2d_to_index(point))
+                .map(|(_, cost)| *cost)
+        };
+        assert_eq!(cost_to(Point::new(3, 1)), Some(1.0));
+        assert_eq!(cost_to(Point::new(4, 2)), Some(1.0));
+        assert_eq!(cost_to(Point::new(5, 1)), Some(4.0));
+        // The walls around the edge of the map can't be dug through
+        assert_eq!(cost_to(Point::new(4, 0)), None);
+    }
+
+    #[test]
+    fn tunnellers_dig_when_it_is_shorter_than_walking_around() {
+        let map = walled_map();
+        let start = map.point2d_to_index(Point::new(4, 1));
+        let end = map.point2d_to_index(Point::new(6, 1));
+
+        // Walking around the wall takes 18 steps, digging through takes 2 steps and digging
+        let distance = |dig_turns| {
+            let tunnels = TunnelMap::new(&map, dig_turns);
+            DijkstraMap::new(map.width, map.height, &[start], &tunnels, 1024.0).map[end]
+        };
+        assert_eq!(distance(2), 4.0);
+        assert_eq!(distance(30), 18.0);
     }
 }
@@ -1,6 +1,13 @@
 use crate::prelude::*;
 
-const NUM_ROOMS: usize = 20;
+// The map area per room built, keeping levels of any size about as crowded
+const TILES_PER_ROOM: i32 = 200;
+// One in this many levels has a shop
+const SHOP_CHANCE: i32 = 3;
+// The largest rooms built, on maps big enough for them
+const MAX_ROOM_SIZE: i32 = 10;
+// The attempts made at placing each room before settling for fewer rooms
+const ROOM_ATTEMPTS: usize = 100;
 
 pub struct MapBuilder {
     // The map building built
@@ -9,35 +16,52 @@ pub struct MapBuilder {
     pub rooms: Vec<Rect>,
     // The location at which the player enters the map
     pub player_start: Point,
+    // The location beside the player at which their companion starts, if there is room
+    pub companion_start: Option<Point>,
     // The location of the Amulet of Yala
     pub amulet_start: Point,
+    // The location beside the amulet at which its guardian waits, if there is room
+    pub boss_start: Option<Point>,
+    // The locations at which items are placed
+    pub item_spawns: Vec<Point>,
+    // The walls on which torches are hung
+    pub torch_spawns: Vec<Point>,
+    // The room set aside as a shop, if the level has one
+    pub shop: Option<Rect>,
 }
 
 impl MapBuilder {
-    pub fn new(rng: &mut RandomNumberGenerator) -> Self {
+    pub fn new(rng: &mut RandomNumberGenerator, width: i32, height: i32) -> Self {
         let mut mb = Self {
-            map: Map::new(),
+            map: Map::new(width, height),
             rooms: Vec::new(),
             player_start: Point::zero(),
+            companion_start: None,
             amulet_start: Point::zero(),
+            boss_start: None,
+            item_spawns: Vec::new(),
+            torch_spawns: Vec::new(),
+            shop: None,
         };
 
         // Mark all tiles in the map as walls
         mb.fill(TileType::Wall);
         // Randomly build a set of rooms in the map
-        log(format!("building rooms count={}", NUM_ROOMS));
-        mb.build_random_rooms(rng);
+        let num_rooms = usize::max(2, (width * height / TILES_PER_ROOM) as usize);
+        log(format!("building rooms count={}", num_rooms));
+        mb.build_random_rooms(rng, num_rooms);
         // Build corridors connecting the rooms
         log("building corridors");
         mb.build_corridors(rng);
         // Mark the player start area as the center of the first room
         mb.player_start = mb.rooms[0].center();
+        mb.companion_start = mb.free_neighbour(mb.player_start);
         // Use a Dijkstra Map (Flow Map) to find ther furthest position
         // from the player starting point.
         let dijkstra_map = DijkstraMap::new(
-            SCREEN_WIDTH,
-            SCREEN_HEIGHT,
-            &vec![mb.map.point2d_to_index(mb.player_start)],
+            mb.map.width,
+            mb.map.height,
+            &[mb.map.point2d_to_index(mb.player_start)],
             &mb.map,
             1024.0,
         );
@@ -52,9 +76,30 @@ impl MapBuilder {
                 .unwrap()
                 .0,
         );
+        // The boss guarding the amulet waits beside it
+        mb.boss_start = mb.free_neighbour(mb.amulet_start);
+        // Occasionally turn one of the rooms into a shop
+        mb.build_shop(rng);
+        // Scatter items throughout the rooms
+        mb.build_item_spawns(rng);
+        // Hang torches in some of the rooms
+        mb.build_torch_spawns(rng);
         mb
     }
 
+    // Find a floor tile next to the position, if there is one
+    fn free_neighbour(&self, position: Point) -> Option<Point> {
+        [
+            Point::new(-1, 0),
+            Point::new(1, 0),
+            Point::new(0, -1),
+            Point::new(0, 1),
+        ]
+        .iter()
+        .map(|delta| position + *delta)
+        .find(|neighbour| self.map.can_enter_tile(*neighbour))
+    }
+
     // Fill the map with the given TileType
     fn fill(&mut self, tile: TileType) {
         self.map.tiles.iter_mut().for_each(|t| *t = tile);
@@ -64,14 +109,27 @@ impl MapBuilder {
      * Given a random generator with a set seed, randomly generate a set
      * of rooms that are not intersecting and create the floors within each room.
      */
-    fn build_random_rooms(&mut self, rng: &mut RandomNumberGenerator) {
-        while self.rooms.len() < NUM_ROOMS {
+    fn build_random_rooms(&mut self, rng: &mut RandomNumberGenerator, num_rooms: usize) {
+        // Scale the rooms down on small maps, keeping them clear of the edge of the map.
+        // Rooms range from two tiles up to one less than the largest size across and down.
+        let max_size = i32::clamp(
+            i32::min(self.map.width, self.map.height) / 3,
+            3,
+            MAX_ROOM_SIZE,
+        );
+
+        // Give up on the remaining rooms if they don't fit without overlapping
+        for _ in 0..num_rooms * ROOM_ATTEMPTS {
+            if self.rooms.len() >= num_rooms {
+                break;
+            }
+
             // Build the room
             let room = Rect::with_size(
-                rng.range(1, SCREEN_WIDTH - 10),
-                rng.range(1, SCREEN_HEIGHT - 10),
-                rng.range(2, 10),
-                rng.range(2, 10),
+                rng.range(1, i32::max(2, self.map.width - max_size)),
+                rng.range(1, i32::max(2, self.map.height - max_size)),
+                rng.range(2, max_size),
+                rng.range(2, max_size),
             );
 
             // Check to see if it overlaps with other rooms
@@ -89,11 +147,11 @@ impl MapBuilder {
             if !overlap {
                 room.for_each(|position| {
                     if position.x > 0
-                        && position.x < SCREEN_WIDTH
+                        && position.x < self.map.width
                         && position.y > 0
-                        && position.y < SCREEN_HEIGHT
+                        && position.y < self.map.height
                     {
-                        let idx = map_idx(position.x, position.y);
+                        let idx = self.map.idx(position.x, position.y);
                         self.map.tiles[idx] = TileType::Floor;
                     }
                 });
@@ -102,6 +160,66 @@ impl MapBuilder {
         }
     }
 
+    /*
+     * Set aside one of the rooms as a shop on some levels. The shop is never the room the
+     * player starts in or the room holding the amulet.
+     */
+    fn build_shop(&mut self, rng: &mut RandomNumberGenerator) {
+        if rng.range(0, SHOP_CHANCE) != 0 {
+            return;
+        }
+
+        let candidates: Vec<Rect> = self
+            .rooms
+            .iter()
+            .skip(1)
+            .filter(|room| !room.point_in_rect(self.amulet_start))
+            .copied()
+            .collect();
+        self.shop = rng.random_slice_entry(&candidates).copied();
+    }
+
+    /*
+     * Randomly choose a location within some of the rooms to place an item. The first
+     * room is skipped as it is where the player starts, and the shop as its merchant
+     * brings their own stock. Room centers are avoided as that is where monsters are
+     * spawned, as is the location of the amulet.
+     */
+    fn build_item_spawns(&mut self, rng: &mut RandomNumberGenerator) {
+        for room in self.rooms.iter().skip(1) {
+            if rng.range(0, 2) == 0 || self.shop == Some(*room) {
+                continue;
+            }
+
+            let position = Point::new(rng.range(room.x1, room.x2), rng.range(room.y1, room.y2));
+            if position != room.center()
+                && position != self.amulet_start
+                && self.map.can_enter_tile(position)
+            {
+                self.item_spawns.push(position);
+            }
+        }
+    }
+
+    /*
+     * Hang a torch on the middle of the top wall of about half of the rooms. Rooms where
+     * a corridor leaves through that spot are left dark.
+     */
+    fn build_torch_spawns(&mut self, rng: &mut RandomNumberGenerator) {
+        for room in self.rooms.iter() {
+            if rng.range(0, 2) == 0 {
+                continue;
+            }
+
+            let position = Point::new(room.center().x, room.y1 - 1);
+            if let Some(idx) = self.map.try_idx(position) {
+                if self.map.tiles[idx] == TileType::Wall {
+                    self.torch_spawns.push(position);
+                }
+            }
+        }
+    }
+
     /*
      * Create a virtical tunnel between two points on a map
      */
@@ -109,7 +227,7 @@ impl MapBuilder {
         use std::cmp::{max, min};
         for y in min(y1, y2)..=max(y1, y2) {
             if let Some(idx) = self.map.try_idx(Point::new(x, y)) {
-                self.map.tiles[idx as usize] = TileType::Floor
+                self.map.tiles[idx] = TileType::Floor
             }
         }
     }
@@ -121,7 +239,7 @@ impl MapBuilder {
         use std::cmp::{max, min};
         for x in min(x1, x2)..=max(x1, x2) {
             if let Some(idx) = self.map.try_idx(Point::new(x, y)) {
-                self.map.tiles[idx as usize] = TileType::Floor;
+                self.map.tiles[idx] = TileType::Floor;
             }
         }
     }
@@ -131,7 +249,7 @@ impl MapBuilder {
         // Sort rooms by their center point before allocating corridors. This
         // makes it more likely that corridors will connect adjacent rooms and not
         // snake across the whole map.
-        rooms.sort_by(|a, b| a.center().x.cmp(&b.center().x));
+        rooms.sort_by_key(|a| a.center().x);
 
         // Iterate through the set of rooms and connect each room with the previous room.
         // Skip the first enumerated room so that previous is a valid index.
@@ -161,3 +279,47 @@ impl MapBuilder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Build maps of the given size with a range of seeds and check they are playable
+    fn check_builds(width: i32, height: i32) {
+        for seed in 0..20 {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let mb = MapBuilder::new(&mut rng, width, height);
+
+            assert!(!mb.rooms.is_empty());
+            mb.rooms.iter().for_each(|room| {
+                assert!(room.x1 > 0 && room.x2 < width - 1);
+                assert!(room.y1 > 0 && room.y2 < height - 1);
+            });
+            assert!(mb.map.can_enter_tile(mb.player_start));
+            assert!(mb.map.can_enter_tile(mb.amulet_start));
+            let distances = DijkstraMap::new(
+                width,
+                height,
+                &[mb.map.point2d_to_index(mb.player_start)],
+                &mb.map,
+                1024.0,
+            );
+            assert!(distances.map[mb.map.point2d_to_index(mb.amulet_start)] < f32::MAX);
+            assert!(mb
+                .item_spawns
+                .iter()
+                .all(|position| mb.map.can_enter_tile(*position)));
+        }
+    }
+
+    #[test]
+    fn builds_small_maps() {
+        check_builds(20, 15);
+        check_builds(12, 10);
+    }
+
+    #[test]
+    fn builds_maps_bigger_than_the_screen() {
+        check_builds(120, 80);
+    }
+}
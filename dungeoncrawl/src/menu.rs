@@ -0,0 +1,68 @@
+use crate::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MenuChoice {
+    NewGame,
+    Continue,
+    Options,
+    Quit,
+    Resume,
+    SaveAndQuit,
+    MovementKeys,
+    Colors,
+    Camera,
+    Back,
+}
+
+/*
+ * A list of choices drawn on the HUD layer. The move up and move down actions change the
+ * selected choice and the confirm action picks it, so the menus follow the key bindings.
+ */
+pub struct Menu {
+    pub selection: usize,
+}
+
+impl Menu {
+    pub fn new() -> Self {
+        Self { selection: 0 }
+    }
+
+    /*
+     * Draw the choices starting at the given row with the selected choice highlighted.
+     * Returns the choice picked this tick, if any.
+     */
+    pub fn show(
+        &mut self,
+        ctx: &mut BTerm,
+        top: i32,
+        choices: &[(MenuChoice, String)],
+        action: Option<Action>,
+    ) -> Option<MenuChoice> {
+        // Keep the selection within the list if the list has changed
+        self.selection = usize::min(self.selection, choices.len() - 1);
+
+        let mut picked = None;
+        match action {
+            Some(Action::MoveUp) => {
+                self.selection = (self.selection + choices.len() - 1) % choices.len();
+            }
+            Some(Action::MoveDown) => {
+                self.selection = (self.selection + 1) % choices.len();
+            }
+            Some(Action::Confirm) => picked = Some(choices[self.selection].0),
+            _ => {}
+        }
+
+        ctx.set_active_console(2);
+        choices.iter().enumerate().for_each(|(i, (_, text))| {
+            let y = top + i as i32 * 2;
+            if i == self.selection {
+                ctx.print_color_centered(y, YELLOW, BLACK, format!("> {} <", text));
+            } else {
+                ctx.print_color_centered(y, WHITE, BLACK, text);
+            }
+        });
+
+        picked
+    }
+}
@@ -0,0 +1,121 @@
+use crate::prelude::*;
+
+// A preset of extra keys used to move the player and the look cursor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MovementKeys {
+    Wasd,
+    Numpad,
+    KeyBindingsOnly,
+}
+
+impl MovementKeys {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MovementKeys::Wasd => "WASD",
+            MovementKeys::Numpad => "Numpad",
+            MovementKeys::KeyBindingsOnly => "Key bindings only",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            MovementKeys::Wasd => MovementKeys::Numpad,
+            MovementKeys::Numpad => MovementKeys::KeyBindingsOnly,
+            MovementKeys::KeyBindingsOnly => MovementKeys::Wasd,
+        }
+    }
+
+    // The keys in the preset along with the movement action they perform
+    pub fn bindings(&self) -> Vec<(VirtualKeyCode, Action)> {
+        match self {
+            MovementKeys::Wasd => vec![
+                (VirtualKeyCode::A, Action::MoveLeft),
+                (VirtualKeyCode::D, Action::MoveRight),
+                (VirtualKeyCode::W, Action::MoveUp),
+                (VirtualKeyCode::S, Action::MoveDown),
+            ],
+            MovementKeys::Numpad => vec![
+                (VirtualKeyCode::Numpad4, Action::MoveLeft),
+                (VirtualKeyCode::Numpad6, Action::MoveRight),
+                (VirtualKeyCode::Numpad8, Action::MoveUp),
+                (VirtualKeyCode::Numpad2, Action::MoveDown),
+            ],
+            MovementKeys::KeyBindingsOnly => Vec::new(),
+        }
+    }
+
+    // Get the movement action for the given key, if it is part of the preset
+    pub fn action(&self, key: VirtualKeyCode) -> Option<Action> {
+        self.bindings()
+            .iter()
+            .find(|(bound, _)| *bound == key)
+            .map(|(_, action)| *action)
+    }
+}
+
+// The colors used to tint the map
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorScheme {
+    Classic,
+    Warm,
+    Cool,
+}
+
+impl ColorScheme {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorScheme::Classic => "Classic",
+            ColorScheme::Warm => "Warm",
+            ColorScheme::Cool => "Cool",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ColorScheme::Classic => ColorScheme::Warm,
+            ColorScheme::Warm => ColorScheme::Cool,
+            ColorScheme::Cool => ColorScheme::Classic,
+        }
+    }
+
+    // The tint of map tiles within the players field of view
+    pub fn visible_tint(&self) -> (u8, u8, u8) {
+        match self {
+            ColorScheme::Classic => WHITE,
+            ColorScheme::Warm => WHEAT,
+            ColorScheme::Cool => LIGHT_CYAN,
+        }
+    }
+
+    // The tint of map tiles that have been revealed but are not currently visible
+    pub fn remembered_tint(&self) -> (u8, u8, u8) {
+        match self {
+            ColorScheme::Classic => DARK_GRAY,
+            ColorScheme::Warm => SADDLE_BROWN,
+            ColorScheme::Cool => STEEL_BLUE,
+        }
+    }
+}
+
+/*
+ * Player chosen options. The options are kept between games and are added as a resource
+ * every tick so systems can read them.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Options {
+    pub movement_keys: MovementKeys,
+    pub color_scheme: ColorScheme,
+    pub camera_mode: CameraMode,
+    pub show_minimap: bool,
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Self {
+            movement_keys: MovementKeys::Wasd,
+            color_scheme: ColorScheme::Classic,
+            camera_mode: CameraMode::Clamped,
+            show_minimap: false,
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::prelude::*;
+use serde::de::DeserializeSeed;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{self, File};
+use std::path::Path;
+
+const SAVE_FILE: &str = "savegame.json";
+
+/*
+ * The contents of the save file. The world is stored as a json value as it can only be
+ * deserialized through the component registry.
+ */
+#[derive(Serialize, Deserialize)]
+struct SaveGame {
+    map: Map,
+    world: serde_json::Value,
+}
+
+/*
+ * Build the registry of components that are saved. Message components such as
+ * WantsToMove and particles only last for part of a turn and are not saved.
+ */
+fn registry() -> Registry<String> {
+    let mut registry = Registry::<String>::default();
+    registry.register::<Point>("point".to_string());
+    registry.register::<Render>("render".to_string());
+    registry.register::<Player>("player".to_string());
+    registry.register::<Enemy>("enemy".to_string());
+    registry.register::<Boss>("boss".to_string());
+    registry.register::<Companion>("companion".to_string());
+    registry.register::<Faction>("faction".to_string());
+    registry.register::<MovingRandomly>("moving_randomly".to_string());
+    registry.register::<Health>("health".to_string());
+    registry.register::<Name>("name".to_string());
+    registry.register::<Unidentified>("unidentified".to_string());
+    registry.register::<ChasingPlayer>("chasing_player".to_string());
+    registry.register::<Item>("item".to_string());
+    registry.register::<AmuletOfYala>("amulet_of_yala".to_string());
+    registry.register::<Carried>("carried".to_string());
+    registry.register::<Gold>("gold".to_string());
+    registry.register::<Price>("price".to_string());
+    registry.register::<Merchant>("merchant".to_string());
+    registry.register::<ProvidesNutrition>("provides_nutrition".to_string());
+    registry.register::<Hunger>("hunger".to_string());
+    registry.register::<Resting>("resting".to_string());
+    registry.register::<AutoExploring>("auto_exploring".to_string());
+    registry.register::<Travelling>("travelling".to_string());
+    registry.register::<Digger>("digger".to_string());
+    registry.register::<Digging>("digging".to_string());
+    registry.register::<FieldOfView>("field_of_view".to_string());
+    registry.register::<LightSource>("light_source".to_string());
+    registry.register::<LastSeen>("last_seen".to_string());
+    registry.register::<Sleeping>("sleeping".to_string());
+    registry.register::<Stealth>("stealth".to_string());
+    registry.register::<Investigating>("investigating".to_string());
+    registry.register::<Energy>("energy".to_string());
+    registry.register::<Acting>("acting".to_string());
+    registry.register::<Hasted>("hasted".to_string());
+    registry.register::<Slowed>("slowed".to_string());
+    registry.register::<ProvidesHaste>("provides_haste".to_string());
+    registry.register::<ProvidesSlow>("provides_slow".to_string());
+    registry.register::<ProvidesDamage>("provides_damage".to_string());
+    registry.register::<ProvidesHealing>("provides_healing".to_string());
+    registry.register::<ProvidesBlink>("provides_blink".to_string());
+    registry.register::<ProvidesFear>("provides_fear".to_string());
+    registry.register::<ProvidesMagicMapping>("provides_magic_mapping".to_string());
+    registry.register::<ProvidesDetectMonsters>("provides_detect_monsters".to_string());
+    registry.register::<ProvidesCharm>("provides_charm".to_string());
+    registry.register::<ProvidesIdentify>("provides_identify".to_string());
+    registry.register::<TeachesSpell>("teaches_spell".to_string());
+    registry.register::<Ranged>("ranged".to_string());
+    registry.register::<AreaOfEffect>("area_of_effect".to_string());
+    registry.register::<LineOfEffect>("line_of_effect".to_string());
+    registry.register::<Charges>("charges".to_string());
+    registry.register::<Spell>("spell".to_string());
+    registry.register::<LearnedBy>("learned_by".to_string());
+    registry.register::<Mana>("mana".to_string());
+    registry.register::<Afraid>("afraid".to_string());
+    registry.register::<DetectingMonsters>("detecting_monsters".to_string());
+    registry
+}
+
+pub fn has_save_game() -> bool {
+    Path::new(SAVE_FILE).exists()
+}
+
+/*
+ * Serialize the world without the message entities and particles. Legion can't serialize
+ * an archetype holding components missing from the registry, so they are filtered out
+ * rather than saved.
+ */
+fn serialize_world(ecs: &World) -> Result<serde_json::Value, Box<dyn Error>> {
+    let saved = !component::<Particle>()
+        & !component::<Noise>()
+        & !component::<WantsToMove>()
+        & !component::<WantsToAttack>()
+        & !component::<WantsToDig>()
+        & !component::<WantsToInteract>()
+        & !component::<ActivateItem>();
+    Ok(serde_json::to_value(
+        ecs.as_serializable(saved, &registry()),
+    )?)
+}
+
+pub fn save_game(ecs: &World, map: &Map) -> Result<(), Box<dyn Error>> {
+    let world = serialize_world(ecs)?;
+    let save_game = SaveGame {
+        map: map.clone(),
+        world,
+    };
+    serde_json::to_writer(File::create(SAVE_FILE)?, &save_game)?;
+    Ok(())
+}
+
+/*
+ * Load the saved game and remove the save file. A saved game can only be continued once.
+ */
+pub fn load_game() -> Result<(World, Map), Box<dyn Error>> {
+    let save_game: SaveGame = serde_json::from_reader(File::open(SAVE_FILE)?)?;
+    let world = registry().as_deserialize().deserialize(save_game.world)?;
+    fs::remove_file(SAVE_FILE)?;
+    Ok((world, save_game.map))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_and_particles_are_not_saved() {
+        let mut ecs = World::default();
+        ecs.push((Player, Point::new(1, 1)));
+        ecs.push((Enemy, Point::new(2, 2), Acting));
+        ecs.push((
+            (),
+            Noise {
+                position: Point::new(1, 1),
+                radius: 5,
+            },
+        ));
+        ecs.push((Particle::new(
+            Point::new(2, 2),
+            ColorPair::new(WHITE, BLACK),
+            to_cp437('*'),
+            100.0,
+        ),));
+
+        let world = serialize_world(&ecs).unwrap();
+        let entities = world["entities"].as_object().unwrap();
+        assert_eq!(entities.len(), 2);
+        assert!(entities
+            .values()
+            .any(|entity| entity.get("enemy").is_some()));
+
+        let restored = registry().as_deserialize().deserialize(world).unwrap();
+        assert_eq!(<&Point>::query().iter(&restored).count(), 2);
+    }
+}
@@ -1,67 +0,0 @@
-use crate::prelude::*;
-
-pub fn spawn_player(ecs: &mut World, position: Point) {
-    log(format!("spawning player at position: {:?}", position));
-    ecs.push((
-        Player,
-        position,
-        Render {
-            color: ColorPair::new(WHITE, BLACK),
-            glyph: to_cp437('@'),
-        },
-        Health {
-            current: 10,
-            max: 10,
-        },
-        FieldOfView::new(8),
-    ));
-}
-
-pub fn spawn_monster(ecs: &mut World, rng: &mut RandomNumberGenerator, position: Point) {
-    log(format!("spawning enemy at position: {:?}", position));
-    let (hp, name, glyph) = match rng.roll_dice(1, 10) {
-        1..=8 => goblin(),
-        _ => orc(),
-    };
-    ecs.push((
-        Enemy,
-        position,
-        Render {
-            color: ColorPair::new(WHITE, BLACK),
-            glyph,
-        },
-        ChasingPlayer {},
-        Health {
-            current: hp,
-            max: hp,
-        },
-        Name { value: name },
-        FieldOfView::new(6),
-    ));
-}
-
-pub fn spawn_amulet_of_yala(ecs: &mut World, position: Point) {
-    log(format!("spawning amulet at position: {:?}", position));
-    ecs.push((
-        Item,
-        AmuletOfYala,
-        position,
-        Render {
-            color: ColorPair::new(WHITE, BLACK),
-            glyph: to_cp437('|'),
-        },
-        Name {
-            value: "Amulet of Yala".to_string(),
-        },
-    ));
-}
-
-// Return the tuple of information representing a goblin
-pub fn goblin() -> (i32, String, FontCharType) {
-    (1, "Goblin".to_string(), to_cp437('g'))
-}
-
-// Return the tuple of information representing an orc
-pub fn orc() -> (i32, String, FontCharType) {
-    (2, "Orc".to_string(), to_cp437('o'))
-}
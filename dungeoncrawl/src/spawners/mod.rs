@@ -0,0 +1,300 @@
+use crate::prelude::*;
+
+mod template;
+
+pub use template::*;
+
+pub fn spawn_player(ecs: &mut World, position: Point) -> Entity {
+    log(format!("spawning player at position: {:?}", position));
+    let player = ecs.push((
+        Player,
+        position,
+        Render {
+            color: ColorPair::new(WHITE, BLACK),
+            glyph: to_cp437('@'),
+        },
+        Health {
+            current: 10,
+            max: 10,
+        },
+        FieldOfView::new(8),
+        Hunger::new(300),
+        Stealth { value: 5 },
+        Energy::new(NORMAL_SPEED),
+    ));
+    if let Some(mut entry) = ecs.entry(player) {
+        entry.add_component(Mana {
+            current: 10,
+            max: 10,
+        });
+        entry.add_component(Gold { amount: 0 });
+        entry.add_component(Faction::Player);
+        // The lantern carried by the player
+        entry.add_component(LightSource {
+            radius: 5,
+            color: RGB::named(LIGHT_YELLOW),
+        });
+    }
+    player
+}
+
+pub fn spawn_monster(ecs: &mut World, rng: &mut RandomNumberGenerator, position: Point) {
+    log(format!("spawning enemy at position: {:?}", position));
+    let monster = match rng.roll_dice(1, 10) {
+        1..=5 => goblin(),
+        6..=7 => bat(),
+        8 => zombie(),
+        9 => rock_worm(),
+        _ => orc(),
+    };
+    let entity = ecs.push((
+        Enemy,
+        position,
+        Render {
+            color: ColorPair::new(WHITE, BLACK),
+            glyph: monster.glyph,
+        },
+        ChasingPlayer {},
+        Health {
+            current: monster.hp,
+            max: monster.hp,
+        },
+        Name {
+            value: monster.name,
+        },
+        FieldOfView::new(6),
+        // Monsters start asleep until they notice the player or hear a noise
+        Sleeping,
+    ));
+    if let Some(mut entry) = ecs.entry(entity) {
+        entry.add_component(Energy::new(monster.speed));
+        entry.add_component(monster.faction);
+        if let Some(light) = monster.light {
+            entry.add_component(light);
+        }
+        if let Some(digger) = monster.digger {
+            entry.add_component(digger);
+        }
+    }
+}
+
+/*
+ * Spawn the Goblin King guarding the Amulet of Yala. Unlike the other monsters it is
+ * awake from the start, waiting for the player to come for the amulet.
+ */
+pub fn spawn_boss(ecs: &mut World, position: Point) {
+    log(format!("spawning boss at position: {:?}", position));
+    let boss = ecs.push((
+        Enemy,
+        Boss {
+            enraged: false,
+            summon_cooldown: BOSS_SUMMON_INTERVAL,
+        },
+        position,
+        Render {
+            color: ColorPair::new(WHITE, BLACK),
+            glyph: to_cp437('G'),
+        },
+        ChasingPlayer {},
+        Health {
+            current: 15,
+            max: 15,
+        },
+        Name {
+            value: "Goblin King".to_string(),
+        },
+        FieldOfView::new(8),
+    ));
+    if let Some(mut entry) = ecs.entry(boss) {
+        entry.add_component(Energy::new(NORMAL_SPEED));
+        entry.add_component(Faction::Goblins);
+    }
+}
+
+/*
+ * Spawn a goblin called to the aid of the boss. Summoned goblins arrive awake and ready
+ * to fight. They are spawned through the command buffer as the boss summons them from
+ * within a system.
+ */
+pub fn summon_goblin(commands: &mut CommandBuffer, position: Point) {
+    log(format!("summoning goblin at position: {:?}", position));
+    let goblin = goblin();
+    let entity = commands.push((
+        Enemy,
+        position,
+        Render {
+            color: ColorPair::new(WHITE, BLACK),
+            glyph: goblin.glyph,
+        },
+        ChasingPlayer {},
+        Health {
+            current: goblin.hp,
+            max: goblin.hp,
+        },
+        Name { value: goblin.name },
+        FieldOfView::new(6),
+        Energy::new(goblin.speed),
+    ));
+    commands.add_component(entity, goblin.faction);
+}
+
+pub fn spawn_amulet_of_yala(ecs: &mut World, position: Point) {
+    log(format!("spawning amulet at position: {:?}", position));
+    ecs.push((
+        Item,
+        AmuletOfYala,
+        position,
+        Render {
+            color: ColorPair::new(WHITE, BLACK),
+            glyph: to_cp437('|'),
+        },
+        Name {
+            value: "Amulet of Yala".to_string(),
+        },
+    ));
+}
+
+/*
+ * Spawn a companion. Companions follow the player around and attack the monsters they
+ * see. They act on their own energy like the monsters.
+ */
+pub fn spawn_companion(ecs: &mut World, position: Point) {
+    log(format!("spawning companion at position: {:?}", position));
+    ecs.push((
+        Companion,
+        position,
+        Render {
+            color: ColorPair::new(WHITE, BLACK),
+            glyph: to_cp437('d'),
+        },
+        Health { current: 6, max: 6 },
+        Name {
+            value: "Hound".to_string(),
+        },
+        FieldOfView::new(6),
+        Energy::new(NORMAL_SPEED),
+        Faction::Player,
+    ));
+}
+
+/*
+ * Spawn a merchant. Merchants aren't hostile, bumping into one opens their shop. They
+ * have health so that they block the way like any other creature.
+ */
+pub fn spawn_merchant(ecs: &mut World, position: Point) -> Entity {
+    log(format!("spawning merchant at position: {:?}", position));
+    ecs.push((
+        Merchant,
+        position,
+        Render {
+            color: ColorPair::new(WHITE, BLACK),
+            glyph: to_cp437('M'),
+        },
+        Name {
+            value: "Merchant".to_string(),
+        },
+        Health {
+            current: 20,
+            max: 20,
+        },
+        Faction::Traders,
+    ))
+}
+
+pub fn spawn_torch(ecs: &mut World, position: Point) {
+    log(format!("spawning torch at position: {:?}", position));
+    ecs.push((
+        position,
+        Name {
+            value: "Wall torch".to_string(),
+        },
+        LightSource {
+            radius: 6,
+            color: RGB::named(GOLD),
+        },
+    ));
+}
+
+/*
+ * The description of a kind of monster. Anything a kind of monster has beyond the
+ * components every monster has, such as a torch or digging through walls, is part of
+ * its definition.
+ */
+pub struct MonsterDefinition {
+    pub hp: i32,
+    pub name: String,
+    pub glyph: FontCharType,
+    pub speed: i32,
+    pub faction: Faction,
+    pub light: Option<LightSource>,
+    pub digger: Option<Digger>,
+}
+
+// Return the definition of a goblin
+pub fn goblin() -> MonsterDefinition {
+    MonsterDefinition {
+        hp: 1,
+        name: "Goblin".to_string(),
+        glyph: to_cp437('g'),
+        speed: NORMAL_SPEED,
+        faction: Faction::Goblins,
+        light: None,
+        digger: None,
+    }
+}
+
+// Return the definition of an orc. Orcs carry torches, lighting up the area around them.
+pub fn orc() -> MonsterDefinition {
+    MonsterDefinition {
+        hp: 2,
+        name: "Orc".to_string(),
+        glyph: to_cp437('o'),
+        speed: NORMAL_SPEED,
+        faction: Faction::Goblins,
+        light: Some(LightSource {
+            radius: 3,
+            color: RGB::named(ORANGE),
+        }),
+        digger: None,
+    }
+}
+
+// Return the definition of a bat. Bats are fast and act twice a turn.
+pub fn bat() -> MonsterDefinition {
+    MonsterDefinition {
+        hp: 1,
+        name: "Bat".to_string(),
+        glyph: to_cp437('b'),
+        speed: NORMAL_SPEED * 2,
+        faction: Faction::Wildlife,
+        light: None,
+        digger: None,
+    }
+}
+
+// Return the definition of a rock worm. Rock worms are slow, but tunnel through the walls
+// towards their prey.
+pub fn rock_worm() -> MonsterDefinition {
+    MonsterDefinition {
+        hp: 2,
+        name: "Rock Worm".to_string(),
+        glyph: to_cp437('w'),
+        speed: NORMAL_SPEED / 2,
+        faction: Faction::Wildlife,
+        light: None,
+        digger: Some(Digger { turns: 2 }),
+    }
+}
+
+// Return the definition of a zombie. Zombies are slow and act every other turn.
+pub fn zombie() -> MonsterDefinition {
+    MonsterDefinition {
+        hp: 3,
+        name: "Zombie".to_string(),
+        glyph: to_cp437('z'),
+        speed: NORMAL_SPEED / 2,
+        faction: Faction::Undead,
+        light: None,
+        digger: None,
+    }
+}
@@ -0,0 +1,280 @@
+use crate::prelude::*;
+use legion::world::Entry;
+use ron::de::from_reader;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+pub enum EntityType {
+    #[default]
+    Item,
+    Spell,
+}
+
+/// A single entity description loaded from the template data file.
+#[derive(Clone, Deserialize, Debug)]
+pub struct Template {
+    // Templates are items unless stated otherwise
+    #[serde(default)]
+    pub entity_type: EntityType,
+    pub name: String,
+    pub glyph: char,
+    // How often the template is chosen relative to the other templates
+    pub frequency: i32,
+    // The list of effects the entity provides, paired with their amount
+    pub provides: Option<Vec<(String, i32)>>,
+    // How far away the effects can be targeted, if they are targeted
+    pub range: Option<i32>,
+    // The radius of the area around the target hit by the effects
+    pub radius: Option<i32>,
+    // Whether the effects hit everything on the line from the user to the target
+    #[serde(default)]
+    pub line: bool,
+    // The number of times an item can be used before it is used up
+    pub charges: Option<i32>,
+    // The kind of appearance an item has until it is identified, such as a potion
+    pub unidentified: Option<String>,
+    // The gold a merchant asks for the item. Items without a price aren't traded.
+    pub price: Option<i32>,
+    // The turns it takes the carrier of a digging tool to dig through a wall
+    pub digs: Option<i32>,
+    // The mana needed to cast a spell
+    pub mana_cost: Option<i32>,
+    // The name of the spell an item teaches
+    pub teaches: Option<String>,
+    // Whether the player knows a spell from the start
+    #[serde(default)]
+    pub known_at_start: bool,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Templates {
+    pub entities: Vec<Template>,
+    // The appearances unidentified items may have, by kind of appearance. The kinds are
+    // kept in order so the same random numbers always give the same appearances.
+    #[serde(default)]
+    pub appearances: BTreeMap<String, Vec<String>>,
+}
+
+impl Templates {
+    pub fn load() -> Self {
+        let file = File::open("resources/template.ron").expect("Failed opening template file");
+        from_reader(file).expect("Unable to load templates")
+    }
+
+    /*
+     * Spawn a randomly chosen template at each of the given spawn points. Templates
+     * with a higher frequency are proportionally more likely to be chosen.
+     */
+    pub fn spawn_entities(
+        &self,
+        ecs: &mut World,
+        rng: &mut RandomNumberGenerator,
+        spawn_points: &[Point],
+        appearances: &HashMap<String, String>,
+    ) {
+        let available_entities = self.available_items(|_| true);
+        spawn_points.iter().for_each(|position| {
+            if let Some(template) = rng.random_slice_entry(&available_entities) {
+                self.spawn_entity(ecs, *position, template, appearances);
+            }
+        });
+    }
+
+    /*
+     * Stock a merchant with the given number of randomly chosen items that have a price.
+     * The stock is carried by the merchant until it is bought.
+     */
+    pub fn spawn_shop_stock(
+        &self,
+        ecs: &mut World,
+        rng: &mut RandomNumberGenerator,
+        merchant: Entity,
+        count: usize,
+        appearances: &HashMap<String, String>,
+    ) {
+        let available_entities = self.available_items(|template| template.price.is_some());
+        for _ in 0..count {
+            if let Some(template) = rng.random_slice_entry(&available_entities) {
+                log(format!("stocking {} in shop", template.name));
+                let item = self.spawn_item(ecs, template, appearances);
+                if let Some(mut entry) = ecs.entry(item) {
+                    entry.add_component(Carried(merchant));
+                }
+            }
+        }
+    }
+
+    /*
+     * The item templates matching the filter, each repeated as many times as its
+     * frequency so that a random choice is weighted by frequency
+     */
+    fn available_items<F>(&self, filter: F) -> Vec<&Template>
+    where
+        F: Fn(&Template) -> bool,
+    {
+        let mut available_entities = Vec::new();
+        self.entities
+            .iter()
+            .filter(|template| template.entity_type == EntityType::Item && filter(template))
+            .for_each(|template| {
+                for _ in 0..template.frequency {
+                    available_entities.push(template);
+                }
+            });
+        available_entities
+    }
+
+    /*
+     * Give each item that starts unidentified an appearance of its kind, shuffled so that
+     * appearances differ from game to game. The game's random number generator is used
+     * so a game with the same seed has the same appearances.
+     */
+    pub fn assign_appearances(&self, rng: &mut RandomNumberGenerator) -> HashMap<String, String> {
+        let mut shuffled = self.appearances.clone();
+        shuffled.values_mut().for_each(|appearances| {
+            for i in (1..appearances.len()).rev() {
+                let j = rng.range(0, i + 1);
+                appearances.swap(i, j);
+            }
+        });
+
+        let mut assigned = HashMap::new();
+        self.entities.iter().for_each(|template| {
+            let appearance = template
+                .unidentified
+                .as_ref()
+                .and_then(|kind| shuffled.get_mut(kind))
+                .and_then(|appearances| appearances.pop());
+            if let Some(appearance) = appearance {
+                assigned.insert(template.name.clone(), appearance);
+            }
+        });
+        assigned
+    }
+
+    fn spawn_entity(
+        &self,
+        ecs: &mut World,
+        position: Point,
+        template: &Template,
+        appearances: &HashMap<String, String>,
+    ) {
+        log(format!(
+            "spawning {} at position: {:?}",
+            template.name, position
+        ));
+        let item = self.spawn_item(ecs, template, appearances);
+        if let Some(mut entry) = ecs.entry(item) {
+            entry.add_component(position);
+        }
+    }
+
+    // Spawn an item from the template without placing it anywhere
+    fn spawn_item(
+        &self,
+        ecs: &mut World,
+        template: &Template,
+        appearances: &HashMap<String, String>,
+    ) -> Entity {
+        let entity = ecs.push((
+            Item,
+            Render {
+                color: ColorPair::new(WHITE, BLACK),
+                glyph: to_cp437(template.glyph),
+            },
+            Name {
+                value: template.name.clone(),
+            },
+        ));
+
+        if let Some(mut entry) = ecs.entry(entity) {
+            add_effects(&mut entry, template);
+            if let Some(appearance) = appearances.get(&template.name) {
+                entry.add_component(Unidentified {
+                    appearance: appearance.clone(),
+                });
+            }
+            if let Some(charges) = template.charges {
+                entry.add_component(Charges {
+                    current: charges,
+                    max: charges,
+                });
+            }
+            if let Some(price) = template.price {
+                entry.add_component(Price { value: price });
+            }
+            if let Some(turns) = template.digs {
+                entry.add_component(Digger { turns });
+            }
+            if let Some(spell) = &template.teaches {
+                entry.add_component(TeachesSpell {
+                    name: spell.clone(),
+                });
+            }
+        }
+        entity
+    }
+
+    /*
+     * Spawn every spell. Spells are not placed on the map, they are learned by reading
+     * books. The spells known from the start are learned by the given entity.
+     */
+    pub fn spawn_spells(&self, ecs: &mut World, learned_by: Entity) {
+        self.entities
+            .iter()
+            .filter(|template| template.entity_type == EntityType::Spell)
+            .for_each(|template| {
+                log(format!("spawning spell {}", template.name));
+                let spell = ecs.push((
+                    Spell {
+                        mana_cost: template.mana_cost.unwrap_or(0),
+                    },
+                    Name {
+                        value: template.name.clone(),
+                    },
+                ));
+                if let Some(mut entry) = ecs.entry(spell) {
+                    add_effects(&mut entry, template);
+                    if template.known_at_start {
+                        entry.add_component(LearnedBy(learned_by));
+                    }
+                }
+            });
+    }
+}
+
+/*
+ * Attach a component for each of the effects the template provides, along with the range
+ * and area they can be targeted at. Items and spells share the same effects.
+ */
+fn add_effects(entry: &mut Entry, template: &Template) {
+    if let Some(range) = template.range {
+        entry.add_component(Ranged { range });
+    }
+    if let Some(radius) = template.radius {
+        entry.add_component(AreaOfEffect { radius });
+    }
+    if template.line {
+        entry.add_component(LineOfEffect);
+    }
+    if let Some(effects) = &template.provides {
+        effects
+            .iter()
+            .for_each(|(provides, amount)| match provides.as_str() {
+                "Nutrition" => entry.add_component(ProvidesNutrition { amount: *amount }),
+                "Haste" => entry.add_component(ProvidesHaste { turns: *amount }),
+                "Slow" => entry.add_component(ProvidesSlow { turns: *amount }),
+                "Damage" => entry.add_component(ProvidesDamage { amount: *amount }),
+                "Healing" => entry.add_component(ProvidesHealing { amount: *amount }),
+                "Blink" => entry.add_component(ProvidesBlink),
+                "Fear" => entry.add_component(ProvidesFear { turns: *amount }),
+                "Identify" => entry.add_component(ProvidesIdentify),
+                "Charm" => entry.add_component(ProvidesCharm),
+                "MagicMapping" => entry.add_component(ProvidesMagicMapping { radius: *amount }),
+                "DetectMonsters" => entry.add_component(ProvidesDetectMonsters { turns: *amount }),
+                _ => log(format!("unknown effect {} on {}", provides, template.name)),
+            });
+    }
+}
@@ -0,0 +1,86 @@
+use crate::prelude::*;
+
+/// System that moves an auto exploring player one step towards the nearest unexplored
+/// tile each turn. Exploring stops when anything hostile comes into view, a new item is seen,
+/// the player is hurt or there is nothing left to explore.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Faction)]
+#[read_component(Item)]
+#[read_component(Health)]
+#[read_component(FieldOfView)]
+#[read_component(AutoExploring)]
+pub fn auto_explore(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] action: &Option<Action>,
+    #[resource] turn_state: &mut TurnState,
+) {
+    // Actions are handled by the player input system
+    if action.is_some() {
+        return;
+    }
+
+    let mut player = <(Entity, &Point, &Health, &FieldOfView, &AutoExploring)>::query()
+        .filter(component::<Player>());
+    let (player_entity, player_position, health, fov, exploring) = match player.iter(ecs).next() {
+        Some(player) => player,
+        None => return,
+    };
+
+    // Check to see if anything should interrupt exploring
+    let monster_in_view = <(Entity, &Point)>::query()
+        .filter(component::<Health>())
+        .iter(ecs)
+        .any(|(entity, position)| {
+            fov.visible_tiles.contains(position) && is_hostile(ecs, *player_entity, *entity)
+        });
+    let new_item_in_view = <(Entity, &Point)>::query()
+        .filter(component::<Item>())
+        .iter(ecs)
+        .any(|(entity, position)| {
+            fov.visible_tiles.contains(position) && !exploring.seen_items.contains(entity)
+        });
+    let hurt = health.current < exploring.health;
+    if monster_in_view || new_item_in_view || hurt {
+        commands.remove_component::<AutoExploring>(*player_entity);
+        return;
+    }
+
+    // Generate a flow map towards every tile that has not been seen. Tiles that are
+    // currently visible are treated as seen as they are revealed after the next move.
+    let search_targets: Vec<usize> = map
+        .revealed_tiles
+        .iter()
+        .enumerate()
+        .filter(|(idx, revealed)| {
+            !**revealed && !fov.visible_tiles.contains(&map.index_to_point2d(*idx))
+        })
+        .map(|(idx, _)| idx)
+        .collect();
+    let dijkstra_map = DijkstraMap::new(map.width, map.height, &search_targets, map, 1024.0);
+
+    // Only step towards unexplored tiles that can be reached
+    let idx = map.idx(player_position.x, player_position.y);
+    let destination = if dijkstra_map.map[idx] < f32::MAX {
+        DijkstraMap::find_lowest_exit(&dijkstra_map, idx, map)
+    } else {
+        None
+    };
+
+    if let Some(destination) = destination {
+        commands.push((
+            (),
+            WantsToMove {
+                entity: *player_entity,
+                destination: map.index_to_point2d(destination),
+            },
+        ));
+        *turn_state = TurnState::PlayerTurn;
+    } else {
+        log("Nothing left to explore");
+        commands.remove_component::<AutoExploring>(*player_entity);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::prelude::*;
+
+// The goblins called to the aid of the boss each time it summons
+const SUMMON_COUNT: usize = 2;
+
+/// System running the phases of the boss fight. Once the boss sees a foe it counts down to
+/// summoning goblins around itself, spending its action to do so. At half health it
+/// enrages, doubling its speed and summoning twice as often.
+#[system]
+#[read_component(Point)]
+#[read_component(Acting)]
+#[read_component(FieldOfView)]
+#[read_component(Health)]
+#[read_component(Faction)]
+#[write_component(Boss)]
+#[write_component(Energy)]
+#[write_component(Render)]
+pub fn boss(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] map: &Map) {
+    // Find the acting bosses that can see something hostile to fight
+    let mut bosses = <(Entity, &Point, &FieldOfView, &Health)>::query()
+        .filter(component::<Boss>() & component::<Acting>());
+    let mut creatures = <(Entity, &Point)>::query().filter(component::<Health>());
+    let engaged: Vec<(Entity, Point, bool)> = bosses
+        .iter(ecs)
+        .filter(|(boss, _, fov, _)| {
+            creatures.iter(ecs).any(|(target, position)| {
+                fov.visible_tiles.contains(position) && is_hostile(ecs, **boss, *target)
+            })
+        })
+        .map(|(boss, position, _, health)| (*boss, *position, health.current * 2 <= health.max))
+        .collect();
+    let mut occupied: Vec<Point> = creatures.iter(ecs).map(|(_, position)| *position).collect();
+
+    engaged.iter().for_each(|(entity, position, wounded)| {
+        let mut entry = ecs.entry_mut(*entity).unwrap();
+
+        // Enrage the first time the boss is wounded down to half health
+        let boss = entry.get_component_mut::<Boss>().unwrap();
+        let enrage = *wounded && !boss.enraged;
+        if enrage {
+            boss.enraged = true;
+            boss.summon_cooldown = i32::min(boss.summon_cooldown, boss.summon_interval());
+        }
+
+        boss.summon_cooldown -= 1;
+        let summon = boss.summon_cooldown < 1;
+        if summon {
+            boss.summon_cooldown = boss.summon_interval();
+        }
+
+        if enrage {
+            log("The boss flies into a rage");
+            if let Ok(energy) = entry.get_component_mut::<Energy>() {
+                energy.speed *= 2;
+            }
+            if let Ok(render) = entry.get_component_mut::<Render>() {
+                render.color.fg = RGBA::named(RED);
+            }
+        }
+
+        // Call goblins to the free tiles around the boss. Summoning takes the boss's
+        // action for the turn.
+        if summon {
+            log("The boss summons goblins to its aid");
+            let free_tiles: Vec<Point> = (-1..=1)
+                .flat_map(|y| (-1..=1).map(move |x| *position + Point::new(x, y)))
+                .filter(|tile| map.can_enter_tile(*tile) && !occupied.contains(tile))
+                .take(SUMMON_COUNT)
+                .collect();
+            free_tiles
+                .iter()
+                .for_each(|tile| summon_goblin(commands, *tile));
+            occupied.extend(free_tiles);
+            commands.remove_component::<Acting>(*entity);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::tests::run_once;
+
+    #[test]
+    fn boss_summons_once_its_cooldown_runs_out() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Map::new(10, 10));
+        let mut fov = FieldOfView::new(8);
+        fov.visible_tiles.insert(Point::new(1, 1));
+        let boss = ecs.push((
+            Boss {
+                enraged: false,
+                summon_cooldown: BOSS_SUMMON_INTERVAL,
+            },
+            Point::new(5, 5),
+            fov,
+            Health {
+                current: 10,
+                max: 10,
+            },
+            Faction::Goblins,
+        ));
+        ecs.push((
+            Player,
+            Point::new(1, 1),
+            Health {
+                current: 10,
+                max: 10,
+            },
+            Faction::Player,
+        ));
+
+        for _ in 1..BOSS_SUMMON_INTERVAL {
+            ecs.entry(boss).unwrap().add_component(Acting);
+            run_once(boss_system(), &mut ecs, &mut resources);
+        }
+        assert_eq!(<&Enemy>::query().iter(&ecs).count(), 0);
+
+        // Summoning takes the boss's action
+        ecs.entry(boss).unwrap().add_component(Acting);
+        run_once(boss_system(), &mut ecs, &mut resources);
+        assert_eq!(<&Enemy>::query().iter(&ecs).count(), SUMMON_COUNT);
+        let entry = ecs.entry_ref(boss).unwrap();
+        assert!(entry.get_component::<Acting>().is_err());
+        assert_eq!(
+            entry.get_component::<Boss>().unwrap().summon_cooldown,
+            BOSS_SUMMON_INTERVAL
+        );
+    }
+
+    #[test]
+    fn wounded_boss_enrages_and_summons_sooner() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Map::new(10, 10));
+        let mut fov = FieldOfView::new(8);
+        fov.visible_tiles.insert(Point::new(1, 1));
+        let boss = ecs.push((
+            Boss {
+                enraged: false,
+                summon_cooldown: BOSS_SUMMON_INTERVAL,
+            },
+            Point::new(5, 5),
+            fov,
+            Health {
+                current: 5,
+                max: 10,
+            },
+            Energy::new(NORMAL_SPEED),
+            Faction::Goblins,
+        ));
+        ecs.push((
+            Player,
+            Point::new(1, 1),
+            Health {
+                current: 10,
+                max: 10,
+            },
+            Faction::Player,
+        ));
+
+        ecs.entry(boss).unwrap().add_component(Acting);
+        run_once(boss_system(), &mut ecs, &mut resources);
+        let entry = ecs.entry_ref(boss).unwrap();
+        assert!(entry.get_component::<Boss>().unwrap().enraged);
+        assert_eq!(
+            entry.get_component::<Energy>().unwrap().speed,
+            NORMAL_SPEED * 2
+        );
+
+        // The cooldown is cut to the enraged interval
+        for _ in 1..BOSS_SUMMON_INTERVAL / 2 {
+            ecs.entry(boss).unwrap().add_component(Acting);
+            run_once(boss_system(), &mut ecs, &mut resources);
+        }
+        assert_eq!(<&Enemy>::query().iter(&ecs).count(), SUMMON_COUNT);
+    }
+}
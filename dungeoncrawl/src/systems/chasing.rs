@@ -3,79 +3,150 @@ use crate::prelude::*;
 #[system]
 #[read_component(Point)]
 #[read_component(ChasingPlayer)]
+#[read_component(Sleeping)]
+#[read_component(Acting)]
+#[read_component(Afraid)]
+#[read_component(Investigating)]
 #[read_component(FieldOfView)]
 #[read_component(Health)]
 #[read_component(Player)]
+#[read_component(Faction)]
+#[read_component(Digger)]
 pub fn chasing(ecs: &SubWorld, commands: &mut CommandBuffer, #[resource] map: &Map) {
-    // Get entities with a point that are chasing the player along with the entities fov
-    let mut movers = <(Entity, &Point, &ChasingPlayer, &FieldOfView)>::query();
+    // Get entities with a point that are chasing along with the entities fov. Sleeping
+    // monsters don't chase.
+    let mut movers = <(Entity, &Point, &ChasingPlayer, &FieldOfView)>::query()
+        .filter(component::<Acting>() & !component::<Sleeping>());
     // Get the positions of entities with health on the map
     let mut positions = <(Entity, &Point, &Health)>::query();
     // Get the position of the Player
     let mut player = <(&Point, &Player)>::query();
+    let player_position = player.iter(ecs).next().unwrap().0;
 
-    // Get the current map position of the player
-    let player_position = player.iter(ecs).nth(0).unwrap().0;
-    let player_idx = map_idx(player_position.x, player_position.y);
+    movers.iter(ecs).for_each(|(entity, position, _, fov)| {
+        let entry = ecs.entry_ref(*entity).unwrap();
+        // Monsters investigating a noise keep at it until they see the player
+        if entry.get_component::<Investigating>().is_ok()
+            && !fov.visible_tiles.contains(player_position)
+        {
+            return;
+        }
 
-    // Generate a flow map to use to hunt a player
-    let search_targets = vec![player_idx];
-    let dijkstra_map = DijkstraMap::new(SCREEN_WIDTH, SCREEN_HEIGHT, &search_targets, map, 1024.0);
+        // Tunnelling entities feel their prey through the rock within the radius of their
+        // field of view and path through the walls in their way
+        let digger = entry.get_component::<Digger>().ok().copied();
+        let tunnels = digger.map(|digger| TunnelMap::new(map, digger.turns));
+        let paths: &dyn BaseMap = match &tunnels {
+            Some(tunnels) => tunnels,
+            None => map,
+        };
+        let senses = |target_position: &Point| match digger {
+            Some(_) => {
+                DistanceAlg::Pythagoras.distance2d(*position, *target_position) <= fov.radius as f32
+            }
+            None => fov.visible_tiles.contains(target_position),
+        };
 
-    movers.iter(ecs).for_each(|(entity, position, _, fov)| {
-        // Only attempt to chase the player if the player is within the field of view
-        // of the entity
-        if !fov.visible_tiles.contains(&player_position) {
+        // Hunt anything hostile within the field of view of the entity, whether it is
+        // the player, a companion or a rival monster
+        let search_targets: Vec<usize> = positions
+            .iter(ecs)
+            .filter(|(target, target_position, _)| {
+                senses(target_position) && is_hostile(ecs, *entity, **target)
+            })
+            .map(|(_, target_position, _)| map.point2d_to_index(*target_position))
+            .collect();
+        if search_targets.is_empty() {
             return;
         }
 
-        let idx = map_idx(position.x, position.y);
-        if let Some(destination) = DijkstraMap::find_lowest_exit(&dijkstra_map, idx, map) {
-            // Calculate the distance and determine the move the monster should make. Prevent the
-            // monster attacking diagonaly by checking the calculated distance
-            let distance = DistanceAlg::Pythagoras.distance2d(*position, *player_position);
-            let destination = if distance > 1.2 {
-                map.index_to_point2d(destination)
-            } else {
-                *player_position
-            };
+        // Generate a flow map to use to hunt the targets
+        let dijkstra_map = DijkstraMap::new(map.width, map.height, &search_targets, paths, 1024.0);
+        let idx = map.idx(position.x, position.y);
+
+        // Frightened monsters run from their targets instead of chasing them
+        if entry.get_component::<Afraid>().is_ok() {
+            if let Some(destination) = DijkstraMap::find_highest_exit(&dijkstra_map, idx, paths) {
+                let destination = map.index_to_point2d(destination);
+                let blocked = positions
+                    .iter(ecs)
+                    .any(|(_, target_position, _)| *target_position == destination);
+                if !blocked {
+                    commands.push((
+                        (),
+                        WantsToMove {
+                            entity: *entity,
+                            destination,
+                        },
+                    ));
+                }
+            }
+            return;
+        }
 
-            let mut attacked = false;
-            positions
-                .iter(ecs)
-                .filter(|(_, target_position, _)| **target_position == destination)
-                .for_each(|(victim, _, _)| {
-                    // Check to see the entity at the intended destination is a player.
-                    // If it is send the message of intent to attack. Track that the entity
-                    // attack to determine if it should move into the destination or not.
-                    if ecs
-                        .entry_ref(*victim)
-                        .unwrap()
-                        .get_component::<Player>()
-                        .is_ok()
-                    {
-                        commands.push((
-                            (),
-                            WantsToAttack {
-                                attacker: *entity,
-                                victim: *victim,
-                            },
-                        ));
-                    }
-                    attacked = true;
-                });
+        // Attack a target next to the entity. Checking the distance prevents the entity
+        // attacking diagonally.
+        let adjacent_target = search_targets
+            .iter()
+            .map(|target_idx| map.index_to_point2d(*target_idx))
+            .find(|target_position| {
+                DistanceAlg::Pythagoras.distance2d(*position, *target_position) < 1.2
+            });
+        let destination = match adjacent_target {
+            Some(target_position) => target_position,
+            None => match DijkstraMap::find_lowest_exit(&dijkstra_map, idx, paths) {
+                Some(destination) => map.index_to_point2d(destination),
+                None => return,
+            },
+        };
 
-            // If the destination does not include a player that was attacked or another enemy
-            // entity send a message of intent to move to the target destination.
-            if !attacked {
+        // Tunnelling entities dig into the walls on their way
+        if let Some(digger) = digger {
+            if map.can_dig(destination) {
                 commands.push((
                     (),
-                    WantsToMove {
-                        entity: *entity,
-                        destination,
+                    WantsToDig {
+                        digger: *entity,
+                        position: destination,
+                        turns: digger.turns,
                     },
                 ));
+                return;
             }
         }
+
+        let mut attacked = false;
+        positions
+            .iter(ecs)
+            .filter(|(_, target_position, _)| **target_position == destination)
+            .for_each(|(victim, _, _)| {
+                // Check to see the entity at the intended destination is hostile.
+                // If it is send the message of intent to attack. Track that the entity
+                // attack to determine if it should move into the destination or not.
+                if is_hostile(ecs, *entity, *victim) {
+                    commands.push((
+                        (),
+                        WantsToAttack {
+                            attacker: *entity,
+                            victim: *victim,
+                            damage: 1,
+                        },
+                    ));
+                }
+                attacked = true;
+            });
+
+        // If the destination does not include a hostile entity that was attacked or
+        // another entity in the way send a message of intent to move to the target
+        // destination.
+        if !attacked {
+            commands.push((
+                (),
+                WantsToMove {
+                    entity: *entity,
+                    destination,
+                },
+            ));
+        }
     })
 }
@@ -1,38 +1,192 @@
 use crate::prelude::*;
 
+// The lifetimes of the combat particles in milliseconds
+const HIT_FLASH_MS: f32 = 200.0;
+const DAMAGE_NUMBER_MS: f32 = 800.0;
+const DEATH_SPLATTER_MS: f32 = 1500.0;
+// How far away the sounds of a fight can be heard
+const COMBAT_NOISE: i32 = 8;
+
 #[system]
+#[read_component(Point)]
 #[read_component(WantsToAttack)]
 #[read_component(Player)]
+#[read_component(Enemy)]
+#[read_component(Hunger)]
+#[read_component(FieldOfView)]
 #[write_component(Health)]
 pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
-    // Get the intended victims of an attack.
+    // Get the attackers and intended victims of an attack.
     let mut attackers = <(Entity, &WantsToAttack)>::query();
-    let victims: Vec<(Entity, Entity)> = attackers
+    let victims: Vec<(Entity, Entity, Entity, i32)> = attackers
         .iter(ecs)
-        .map(|(entity, attack)| (*entity, attack.victim))
+        .map(|(entity, attack)| (*entity, attack.attacker, attack.victim, attack.damage))
         .collect();
 
-    victims.iter().for_each(|(message, victim)| {
-        let is_player = ecs
-            .entry_ref(*victim)
-            .unwrap()
-            .get_component::<Player>()
-            .is_ok();
-        if let Ok(mut health) = ecs
-            .entry_mut(*victim)
-            .unwrap()
-            .get_component_mut::<Health>()
-        {
-            log(format!("Health before attack: {}", health.current));
-            health.current -= 1;
-            // If the victims health will reach zero remove it from the system.
-            // Don't remove the entity if it is the player.
-            if health.current < 1 && !is_player {
-                commands.remove(*victim);
+    // Only fights the player can see light up, so particles don't give away the
+    // positions of monsters out of view
+    let player_view = <&FieldOfView>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .map(|fov| fov.visible_tiles.clone())
+        .unwrap_or_default();
+
+    let mut rng = RandomNumberGenerator::new();
+    victims
+        .iter()
+        .for_each(|(message, attacker, victim, damage)| {
+            // Fighting is noisy, whether or not the attack lands
+            if let Ok(position) = ecs.entry_ref(*victim).unwrap().get_component::<Point>() {
+                commands.push((
+                    (),
+                    Noise {
+                        position: *position,
+                        radius: COMBAT_NOISE,
+                    },
+                ));
+            }
+
+            // Attackers weakened by hunger miss half of their attacks
+            let is_weak = ecs
+                .entry_ref(*attacker)
+                .map(|entry| {
+                    entry
+                        .get_component::<Hunger>()
+                        .is_ok_and(|hunger| hunger.state() >= HungerState::Weak)
+                })
+                .unwrap_or(false);
+            if is_weak && rng.range(0, 2) == 0 {
+                log("Attack missed due to hunger");
+                commands.remove(*message);
+                return;
             }
-            log(format!("Health after attack: {}", health.current));
-        }
-        // Remove the WantToAttack message
-        commands.remove(*message);
-    });
+
+            let is_player = ecs
+                .entry_ref(*victim)
+                .unwrap()
+                .get_component::<Player>()
+                .is_ok();
+            let is_enemy = ecs
+                .entry_ref(*victim)
+                .unwrap()
+                .get_component::<Enemy>()
+                .is_ok();
+            if let Ok(health) = ecs
+                .entry_mut(*victim)
+                .unwrap()
+                .get_component_mut::<Health>()
+            {
+                log(format!("Health before attack: {}", health.current));
+                // A victim already slain by an earlier attack this turn can't die again
+                let was_alive = health.current >= 1;
+                health.current -= damage;
+                let killed = was_alive && health.current < 1;
+                // If the victims health will reach zero remove it from the system.
+                // Don't remove the entity if it is the player.
+                if killed && !is_player {
+                    commands.remove(*victim);
+                }
+                log(format!("Health after attack: {}", health.current));
+                let max_health = health.max;
+
+                if let Ok(position) = ecs.entry_ref(*victim).unwrap().get_component::<Point>() {
+                    if player_view.contains(position) {
+                        spawn_hit_particles(commands, *position, *damage);
+                        if killed {
+                            spawn_death_particles(commands, *position);
+                        }
+                    }
+                    // Slain monsters drop a pile of gold, more for tougher monsters
+                    if killed && is_enemy {
+                        drop_gold(commands, *position, rng.roll_dice(max_health, 6));
+                    }
+                }
+            }
+            // Remove the WantToAttack message
+            commands.remove(*message);
+        });
+}
+
+/*
+ * Flash the tile that was hit and float the damage dealt upward from above it
+ */
+fn spawn_hit_particles(commands: &mut CommandBuffer, position: Point, damage: i32) {
+    commands.push((
+        (),
+        Particle::new(
+            position,
+            ColorPair::new(ORANGE, BLACK),
+            to_cp437('*'),
+            HIT_FLASH_MS,
+        ),
+    ));
+    format!("{}", damage)
+        .chars()
+        .enumerate()
+        .for_each(|(i, digit)| {
+            let mut particle = Particle::new(
+                position + Point::new(i as i32, -1),
+                ColorPair::new(RED, BLACK),
+                to_cp437(digit),
+                DAMAGE_NUMBER_MS,
+            );
+            particle.rise = 1;
+            commands.push(((), particle));
+        });
+}
+
+fn spawn_death_particles(commands: &mut CommandBuffer, position: Point) {
+    commands.push((
+        (),
+        Particle::new(
+            position,
+            ColorPair::new(DARK_RED, BLACK),
+            to_cp437(','),
+            DEATH_SPLATTER_MS,
+        ),
+    ));
+}
+
+fn drop_gold(commands: &mut CommandBuffer, position: Point, amount: i32) {
+    commands.push((
+        Item,
+        position,
+        Render {
+            color: ColorPair::new(GOLD, BLACK),
+            glyph: to_cp437('$'),
+        },
+        Name {
+            value: format!("{} gold", amount),
+        },
+        Gold { amount },
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::tests::run_once;
+
+    #[test]
+    fn a_victim_killed_twice_drops_gold_once() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        let player = ecs.push((Player, Point::new(4, 5)));
+        let goblin = ecs.push((Enemy, Point::new(5, 5), Health { current: 1, max: 1 }));
+        (0..2).for_each(|_| {
+            ecs.push((
+                (),
+                WantsToAttack {
+                    attacker: player,
+                    victim: goblin,
+                    damage: 1,
+                },
+            ));
+        });
+        run_once(combat_system(), &mut ecs, &mut resources);
+
+        assert!(ecs.entry_ref(goblin).is_err());
+        assert_eq!(<&Gold>::query().iter(&ecs).count(), 1);
+    }
 }
@@ -0,0 +1,89 @@
+use crate::prelude::*;
+
+// How far a companion lets the player get away before following them
+const FOLLOW_DISTANCE: f32 = 2.0;
+
+/// System that moves the companions acting this phase. A companion attacks a hostile
+/// entity next to it, heads for hostile entities it can see and otherwise follows the
+/// player. Companions find their way using Dijkstra maps towards their targets.
+#[system]
+#[read_component(Point)]
+#[read_component(Companion)]
+#[read_component(Acting)]
+#[read_component(FieldOfView)]
+#[read_component(Health)]
+#[read_component(Player)]
+#[read_component(Faction)]
+pub fn companions(ecs: &SubWorld, commands: &mut CommandBuffer, #[resource] map: &Map) {
+    let mut companions = <(Entity, &Point, &FieldOfView)>::query()
+        .filter(component::<Companion>() & component::<Acting>());
+    let mut positions = <(Entity, &Point)>::query().filter(component::<Health>());
+    let mut player = <&Point>::query().filter(component::<Player>());
+    let player_position = *player.iter(ecs).next().unwrap();
+
+    // Generate a flow map leading to the player for the companions to follow
+    let player_map = DijkstraMap::new(
+        map.width,
+        map.height,
+        &[map.point2d_to_index(player_position)],
+        map,
+        1024.0,
+    );
+
+    companions.iter(ecs).for_each(|(entity, position, fov)| {
+        let foes: Vec<(Entity, Point)> = positions
+            .iter(ecs)
+            .filter(|(other, other_position)| {
+                fov.visible_tiles.contains(other_position) && is_hostile(ecs, *entity, **other)
+            })
+            .map(|(other, other_position)| (*other, *other_position))
+            .collect();
+
+        // Attack a foe that is next to the companion, without attacking diagonally
+        let adjacent_foe = foes.iter().find(|(_, foe_position)| {
+            DistanceAlg::Pythagoras.distance2d(*position, *foe_position) < 1.2
+        });
+        if let Some((foe, _)) = adjacent_foe {
+            commands.push((
+                (),
+                WantsToAttack {
+                    attacker: *entity,
+                    victim: *foe,
+                    damage: 1,
+                },
+            ));
+            return;
+        }
+
+        let idx = map.point2d_to_index(*position);
+        let destination = if !foes.is_empty() {
+            let foe_targets: Vec<usize> = foes
+                .iter()
+                .map(|(_, foe_position)| map.point2d_to_index(*foe_position))
+                .collect();
+            let foe_map = DijkstraMap::new(map.width, map.height, &foe_targets, map, 1024.0);
+            DijkstraMap::find_lowest_exit(&foe_map, idx, map)
+        } else if DistanceAlg::Pythagoras.distance2d(*position, player_position) > FOLLOW_DISTANCE {
+            DijkstraMap::find_lowest_exit(&player_map, idx, map)
+        } else {
+            None
+        };
+
+        // Companions step around anyone in their way rather than pushing through
+        if let Some(destination) = destination {
+            let destination = map.index_to_point2d(destination);
+            let blocked = positions
+                .iter(ecs)
+                .any(|(_, other_position)| *other_position == destination);
+            if !blocked {
+                commands.push((
+                    (),
+                    WantsToMove {
+                        entity: *entity,
+                        destination,
+                    },
+                ));
+            }
+        }
+    });
+}
@@ -0,0 +1,128 @@
+use crate::prelude::*;
+
+// How far away the sound of digging can be heard
+const DIGGING_NOISE: i32 = 5;
+
+/// System that processes WantsToDig messages. Each message is a turn spent digging into a
+/// wall. Progress is kept while the digger keeps at the same wall, and once enough turns
+/// are spent the wall is turned into floor. Digging somewhere else starts over.
+#[system(for_each)]
+#[read_component(Digging)]
+pub fn dig(
+    entity: &Entity,
+    message: &WantsToDig,
+    #[resource] map: &mut Map,
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    // Remove messages that have been processed for the entity
+    commands.remove(*entity);
+    if !map.can_dig(message.position) {
+        return;
+    }
+
+    let progress = ecs
+        .entry_ref(message.digger)
+        .ok()
+        .and_then(|digger| digger.get_component::<Digging>().ok().copied())
+        .filter(|digging| digging.position == message.position)
+        .map_or(1, |digging| digging.progress + 1);
+
+    if progress >= message.turns {
+        log(format!("Dug through the wall at {:?}", message.position));
+        map.set_tile(message.position, TileType::Floor);
+        commands.remove_component::<Digging>(message.digger);
+    } else {
+        log(format!(
+            "Digging progress: {} / {}",
+            progress, message.turns
+        ));
+        commands.add_component(
+            message.digger,
+            Digging {
+                position: message.position,
+                progress,
+            },
+        );
+    }
+
+    // Digging is noisy work
+    commands.push((
+        (),
+        Noise {
+            position: message.position,
+            radius: DIGGING_NOISE,
+        },
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::tests::run_once;
+
+    #[test]
+    fn wall_turns_to_floor_after_enough_turns() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        let wall = Point::new(5, 5);
+        let mut map = Map::new(10, 10);
+        map.set_tile(wall, TileType::Wall);
+        resources.insert(map);
+        let digger = ecs.push((Point::new(4, 5),));
+        let message = WantsToDig {
+            digger,
+            position: wall,
+            turns: 3,
+        };
+
+        for _ in 0..2 {
+            ecs.push(((), message));
+            run_once(dig_system(), &mut ecs, &mut resources);
+        }
+        let digging = *ecs
+            .entry_ref(digger)
+            .unwrap()
+            .get_component::<Digging>()
+            .unwrap();
+        assert_eq!(digging.progress, 2);
+        assert!(resources.get::<Map>().unwrap().can_dig(wall));
+
+        ecs.push(((), message));
+        run_once(dig_system(), &mut ecs, &mut resources);
+        let entry = ecs.entry_ref(digger).unwrap();
+        assert!(entry.get_component::<Digging>().is_err());
+        assert!(resources.get::<Map>().unwrap().can_enter_tile(wall));
+    }
+
+    #[test]
+    fn digging_another_wall_starts_over() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        let mut map = Map::new(10, 10);
+        map.set_tile(Point::new(5, 5), TileType::Wall);
+        map.set_tile(Point::new(5, 6), TileType::Wall);
+        resources.insert(map);
+        let digger = ecs.push((Point::new(4, 5),));
+
+        [Point::new(5, 5), Point::new(5, 5), Point::new(5, 6)]
+            .iter()
+            .for_each(|wall| {
+                let message = WantsToDig {
+                    digger,
+                    position: *wall,
+                    turns: 3,
+                };
+                ecs.push(((), message));
+                run_once(dig_system(), &mut ecs, &mut resources);
+            });
+        let digging = *ecs
+            .entry_ref(digger)
+            .unwrap()
+            .get_component::<Digging>()
+            .unwrap();
+        assert_eq!(digging.position, Point::new(5, 6));
+        assert_eq!(digging.progress, 1);
+        assert!(resources.get::<Map>().unwrap().can_dig(Point::new(5, 5)));
+    }
+}
@@ -1,23 +1,96 @@
 use crate::prelude::*;
 
+// The mana regained at the end of each player turn
+const MANA_REGEN: i32 = 1;
+
 #[system]
-#[read_component(Health)]
+#[write_component(Health)]
+#[write_component(Hunger)]
+#[write_component(Energy)]
+#[write_component(Hasted)]
+#[write_component(Slowed)]
+#[write_component(Mana)]
+#[write_component(Afraid)]
+#[write_component(DetectingMonsters)]
 #[read_component(Point)]
 #[read_component(Player)]
 #[read_component(AmuletOfYala)]
-pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState) {
+#[read_component(Boss)]
+pub fn end_turn(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] turn_state: &mut TurnState,
+) {
     let mut player = <(&Health, &Point)>::query().filter(component::<Player>());
     let mut amulet = <&Point>::query().filter(component::<AmuletOfYala>());
-    let current_state = turn_state.clone();
+    let current_state = *turn_state;
     let mut new_state = match current_state {
         TurnState::AwaitingInput => return,
         TurnState::PlayerTurn => TurnState::MonsterTurn,
-        TurnState::MonsterTurn => TurnState::AwaitingInput,
+        // Monsters and companions with energy left over act again before the player
+        TurnState::MonsterTurn => {
+            let monster_can_act = <&Energy>::query()
+                .filter(!component::<Player>())
+                .iter(ecs)
+                .any(|energy| energy.can_act());
+            if monster_can_act {
+                TurnState::MonsterTurn
+            } else {
+                TurnState::AwaitingInput
+            }
+        }
         _ => current_state,
     };
 
-    // Get the position of the amulet
-    let amulet_position = amulet.iter(ecs).nth(0).unwrap();
+    // Tick down the hunger clock once at the end of each player turn. Entities that
+    // are starving take damage every turn until they eat.
+    if current_state == TurnState::PlayerTurn {
+        <(&mut Hunger, &mut Health)>::query()
+            .iter_mut(ecs)
+            .for_each(|(hunger, health)| {
+                hunger.current = i32::max(0, hunger.current - 1);
+                if hunger.state() == HungerState::Starving {
+                    health.current -= 1;
+                    log(format!("Starving health: {}", health.current));
+                }
+            });
+    }
+
+    // Monsters and companions build up energy for the time taken by the player's action, then timed
+    // effects wear off.
+    if current_state == TurnState::PlayerTurn {
+        let player_speed = <(&Energy, Option<&Hasted>, Option<&Slowed>)>::query()
+            .filter(component::<Player>())
+            .iter(ecs)
+            .map(|(energy, hasted, slowed)| {
+                energy.effective_speed(hasted.is_some(), slowed.is_some())
+            })
+            .next()
+            .unwrap_or(NORMAL_SPEED);
+        <(&mut Energy, Option<&Hasted>, Option<&Slowed>)>::query()
+            .filter(!component::<Player>())
+            .iter_mut(ecs)
+            .for_each(|(energy, hasted, slowed)| {
+                let speed = energy.effective_speed(hasted.is_some(), slowed.is_some());
+                energy.gain(speed, player_speed);
+            });
+
+        wear_off::<Hasted>(ecs, commands);
+        wear_off::<Slowed>(ecs, commands);
+        wear_off::<Afraid>(ecs, commands);
+        wear_off::<DetectingMonsters>(ecs, commands);
+    }
+
+    // Mana regenerates slowly at the end of each player turn
+    if current_state == TurnState::PlayerTurn {
+        <&mut Mana>::query().iter_mut(ecs).for_each(|mana| {
+            mana.current = i32::min(mana.max, mana.current + MANA_REGEN);
+        });
+    }
+
+    // Get the position of the amulet. It can only be claimed once its guardian is dead.
+    let amulet_position = amulet.iter(ecs).next().unwrap();
+    let guarded = <&Boss>::query().iter(ecs).next().is_some();
 
     player.iter(ecs).for_each(|(hp, position)| {
         // If the players health has dropped to zero set the state to game over
@@ -25,11 +98,27 @@ pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState) {
             new_state = TurnState::GameOver;
         }
 
-        // If the player has reached the amulet set the state to victory
-        if position == amulet_position {
+        // If the player has reached the unguarded amulet set the state to victory
+        if position == amulet_position && !guarded {
             new_state = TurnState::Victory;
         }
     });
 
     *turn_state = new_state;
 }
+
+/*
+ * Take a turn off of every timed effect of the given kind, removing the effects that
+ * have run out
+ */
+fn wear_off<T: TimedEffect + storage::Component>(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+    <(Entity, &mut T)>::query()
+        .iter_mut(ecs)
+        .for_each(|(entity, effect)| {
+            let turns = effect.turns_left();
+            *turns -= 1;
+            if *turns < 1 {
+                commands.remove_component::<T>(*entity);
+            }
+        });
+}
@@ -0,0 +1,20 @@
+use crate::prelude::*;
+
+/// System that picks the monsters and companions acting in this monster phase. Those with
+/// enough energy spend it and are marked as acting, the rest sit the phase out. The end
+/// turn system runs another phase while any of them still has enough energy to act.
+#[system]
+#[read_component(Player)]
+#[write_component(Energy)]
+pub fn energy(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+    <(Entity, &mut Energy)>::query()
+        .filter(!component::<Player>())
+        .iter_mut(ecs)
+        .for_each(|(entity, energy)| {
+            if energy.try_act() {
+                commands.add_component(*entity, Acting);
+            } else {
+                commands.remove_component::<Acting>(*entity);
+            }
+        });
+}
@@ -3,12 +3,20 @@ use crate::prelude::*;
 #[system]
 #[read_component(Point)]
 #[read_component(Render)]
+#[read_component(LastSeen)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
-pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera) {
+#[read_component(Enemy)]
+#[read_component(DetectingMonsters)]
+pub fn entity_render(
+    ecs: &SubWorld,
+    #[resource] camera: &Camera,
+    #[resource] lighting: &Lighting,
+    #[resource] options: &Options,
+) {
     let mut renderables = <(&Point, &Render)>::query();
     let mut fov = <&FieldOfView>::query().filter(component::<Player>());
-    let player_fov = fov.iter(ecs).nth(0).unwrap();
+    let player_fov = fov.iter(ecs).next().unwrap();
 
     // Create new draw batch. This will batch deferred rendering commands
     let mut draw_batch = DrawBatch::new();
@@ -17,12 +25,44 @@ pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera) {
     // Get the camera offset
     let offset = Point::new(camera.left_x, camera.top_y);
 
+    // Draw entities that are out of view where they were last seen, dimmed like the
+    // remembered tiles they are on.
+    let remembered_color = ColorPair::new(options.color_scheme.remembered_tint(), BLACK);
+    <(Option<&Point>, &LastSeen, &Render)>::query()
+        .iter(ecs)
+        .filter(|(position, last_seen, _)| {
+            !position.is_some_and(|position| player_fov.visible_tiles.contains(position))
+                && !player_fov.visible_tiles.contains(&last_seen.position)
+        })
+        .for_each(|(_, last_seen, render)| {
+            draw_batch.set(last_seen.position - offset, remembered_color, render.glyph);
+        });
+
+    // Monsters sensed by magic are drawn wherever they are while out of view
+    let detecting = <&DetectingMonsters>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .is_some();
+    if detecting {
+        let detected_color = ColorPair::new(MAGENTA, BLACK);
+        <(&Point, &Render)>::query()
+            .filter(component::<Enemy>())
+            .iter(ecs)
+            .filter(|(position, _)| !player_fov.visible_tiles.contains(position))
+            .for_each(|(position, render)| {
+                draw_batch.set(*position - offset, detected_color, render.glyph);
+            });
+    }
+
     // Query for all entities that are renderable AND within the players fov.
     renderables
         .iter(ecs)
-        .filter(|(position, _)| player_fov.visible_tiles.contains(&position))
+        .filter(|(position, _)| player_fov.visible_tiles.contains(position))
         .for_each(|(position, render)| {
-            draw_batch.set(*position - offset, render.color, render.glyph);
+            // Shade the entity by the light falling on it
+            let color = ColorPair::new(lighting.tint(*position, render.color.fg), render.color.bg);
+            draw_batch.set(*position - offset, color, render.glyph);
         });
 
     // Use 5000 as the map may contain 4000 elements. Include an additional offset
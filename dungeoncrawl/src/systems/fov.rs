@@ -2,18 +2,37 @@ use crate::prelude::*;
 
 #[system]
 #[read_component(Point)]
+#[read_component(Player)]
 #[write_component(FieldOfView)]
-pub fn fov(ecs: &mut SubWorld, #[resource] map: &Map) {
+pub fn fov(ecs: &mut SubWorld, #[resource] map: &mut Map, #[resource] lighting: &Lighting) {
     // Get the Point and FieldOfView component of all entities
-    let mut views = <(&Point, &mut FieldOfView)>::query();
+    let mut views = <(&Point, &mut FieldOfView)>::query().filter(!component::<Player>());
 
     // Update the field of view for all entities that
     // need their field of view updated
     views
         .iter_mut(ecs)
         .filter(|(_, fov)| fov.is_dirty)
-        .for_each(|(position, mut fov)| {
+        .for_each(|(position, fov)| {
             fov.visible_tiles = field_of_view_set(*position, fov.radius, map);
             fov.is_dirty = false;
         });
+
+    // The player can only see the lit tiles in line of sight, and reveals them on the map
+    <(&Point, &mut FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter_mut(ecs)
+        .filter(|(_, fov)| fov.is_dirty)
+        .for_each(|(position, fov)| {
+            fov.visible_tiles = field_of_view_set(*position, fov.radius, map)
+                .into_iter()
+                .filter(|tile| lighting.is_lit(*tile))
+                .collect();
+            fov.is_dirty = false;
+            fov.visible_tiles.iter().for_each(|tile| {
+                if let Some(idx) = map.try_idx(*tile) {
+                    map.reveal(idx);
+                }
+            });
+        });
 }
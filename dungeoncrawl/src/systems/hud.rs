@@ -2,18 +2,35 @@ use crate::prelude::*;
 
 #[system]
 #[read_component(Health)]
+#[read_component(Hunger)]
+#[read_component(Mana)]
+#[read_component(Gold)]
+#[read_component(Hasted)]
+#[read_component(Slowed)]
+#[read_component(DetectingMonsters)]
 #[read_component(Player)]
+#[read_component(Item)]
+#[read_component(Carried)]
+#[read_component(Name)]
+#[read_component(Charges)]
+#[read_component(Unidentified)]
+#[read_component(Boss)]
+#[read_component(Point)]
+#[read_component(FieldOfView)]
 pub fn hud(ecs: &SubWorld) {
     // Query for the health component, filtered to player components
-    let mut health_query = <&Health>::query().filter(component::<Player>());
+    let mut health_query = <(Entity, &Health, &Hunger)>::query().filter(component::<Player>());
 
-    // Currenth there is only one Player, get its health
-    let player_health = health_query.iter(ecs).nth(0).unwrap();
+    // Currenth there is only one Player, get its health and hunger
+    let (player, player_health, player_hunger) = health_query.iter(ecs).next().unwrap();
 
     // Batch all draws and set the targe to the HUD layer
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(2);
-    draw_batch.print_centered(1, "Explore the Dungeon. Cursor keys to move.");
+    draw_batch.print_centered(
+        1,
+        "Explore the Dungeon. Key bindings are listed in the options of the pause menu.",
+    );
     draw_batch.bar_horizontal(
         Point::zero(),
         SCREEN_WIDTH * 2,
@@ -29,5 +46,113 @@ pub fn hud(ecs: &SubWorld) {
         ),
         ColorPair::new(WHITE, RED),
     );
+
+    // Display the mana of the player below the health bar
+    if let Ok(mana) = ecs.entry_ref(*player).unwrap().get_component::<Mana>() {
+        draw_batch.print_color(
+            Point::new(1, 1),
+            format!("Mana: {} / {}", mana.current, mana.max),
+            ColorPair::new(CYAN, BLACK),
+        );
+    }
+
+    // Display the gold carried by the player next to the mana
+    if let Ok(gold) = ecs.entry_ref(*player).unwrap().get_component::<Gold>() {
+        draw_batch.print_color(
+            Point::new(20, 1),
+            format!("Gold: {}", gold.amount),
+            ColorPair::new(GOLD, BLACK),
+        );
+    }
+
+    // Display the current hunger state of the player
+    let (hunger_text, hunger_color) = match player_hunger.state() {
+        HungerState::WellFed => ("Well Fed", GREEN),
+        HungerState::Hungry => ("Hungry", YELLOW),
+        HungerState::Weak => ("Weak", ORANGE),
+        HungerState::Starving => ("Starving", RED),
+    };
+    draw_batch.print_color_right(
+        Point::new(SCREEN_WIDTH * 2, 1),
+        hunger_text,
+        ColorPair::new(hunger_color, BLACK),
+    );
+
+    // Display any timed effects on the player below the hunger state
+    let player_entry = ecs.entry_ref(*player).unwrap();
+    let mut effects = Vec::new();
+    if let Ok(hasted) = player_entry.get_component::<Hasted>() {
+        effects.push((format!("Hasted ({})", hasted.turns), CYAN));
+    }
+    if let Ok(slowed) = player_entry.get_component::<Slowed>() {
+        effects.push((format!("Slowed ({})", slowed.turns), PURPLE));
+    }
+    if let Ok(detecting) = player_entry.get_component::<DetectingMonsters>() {
+        effects.push((format!("Detecting ({})", detecting.turns), MAGENTA));
+    }
+    effects.iter().enumerate().for_each(|(i, (text, color))| {
+        draw_batch.print_color_right(
+            Point::new(SCREEN_WIDTH * 2, 2 + i as i32),
+            text,
+            ColorPair::new(*color, BLACK),
+        );
+    });
+
+    // List the items carried by the player along with the key used to activate them,
+    // along with the charges left in items that have them
+    let mut carried = <(&Name, &Carried, Option<&Charges>, Option<&Unidentified>)>::query()
+        .filter(component::<Item>());
+    let mut y = 3;
+    carried
+        .iter(ecs)
+        .filter(|(_, carried, _, _)| carried.0 == *player)
+        .enumerate()
+        .for_each(|(slot, (name, _, charges, unidentified))| {
+            let name = name.shown(unidentified);
+            let text = match charges {
+                Some(charges) => format!(
+                    "{} : {} ({}/{})",
+                    slot + 1,
+                    name,
+                    charges.current,
+                    charges.max
+                ),
+                None => format!("{} : {}", slot + 1, name),
+            };
+            draw_batch.print(Point::new(3, y), text);
+            y += 1;
+        });
+    if y > 3 {
+        draw_batch.print_color(
+            Point::new(3, 2),
+            "Items carried",
+            ColorPair::new(YELLOW, BLACK),
+        );
+    }
+
+    // Display the health of the boss along the bottom of the screen while it is in view
+    let player_fov = player_entry.get_component::<FieldOfView>().ok();
+    <(&Point, &Health, &Name)>::query()
+        .filter(component::<Boss>())
+        .iter(ecs)
+        .filter(|(position, _, _)| {
+            player_fov.is_some_and(|fov| fov.visible_tiles.contains(position))
+        })
+        .for_each(|(_, health, name)| {
+            let y = SCREEN_HEIGHT * 2 - 2;
+            draw_batch.bar_horizontal(
+                Point::new(SCREEN_WIDTH / 2, y),
+                SCREEN_WIDTH,
+                health.current,
+                health.max,
+                ColorPair::new(PURPLE, BLACK),
+            );
+            draw_batch.print_color_centered(
+                y,
+                format!(" {}: {} / {} ", name.value, health.current, health.max),
+                ColorPair::new(WHITE, PURPLE),
+            );
+        });
+
     draw_batch.submit(10000).expect("Batch error");
 }
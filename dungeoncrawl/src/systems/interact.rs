@@ -0,0 +1,51 @@
+use crate::prelude::*;
+
+/// System that processes WantsToInteract messages. Bumping into a merchant opens their
+/// shop without using a turn. Bumping into a companion swaps places with them. Other
+/// entities have nothing to offer.
+#[system(for_each)]
+#[read_component(Merchant)]
+#[read_component(Companion)]
+#[read_component(Point)]
+pub fn interact(
+    entity: &Entity,
+    message: &WantsToInteract,
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] turn_state: &mut TurnState,
+) {
+    if let Ok(target) = ecs.entry_ref(message.target) {
+        if target.get_component::<Merchant>().is_ok() {
+            *turn_state = TurnState::Shop {
+                merchant: message.target,
+                selection: 0,
+                selling: false,
+            };
+        } else if target.get_component::<Companion>().is_ok() {
+            let actor_position = ecs
+                .entry_ref(message.actor)
+                .ok()
+                .and_then(|actor| actor.get_component::<Point>().ok().copied());
+            let target_position = target.get_component::<Point>().ok().copied();
+            if let (Some(actor_position), Some(target_position)) = (actor_position, target_position)
+            {
+                commands.push((
+                    (),
+                    WantsToMove {
+                        entity: message.actor,
+                        destination: target_position,
+                    },
+                ));
+                commands.push((
+                    (),
+                    WantsToMove {
+                        entity: message.target,
+                        destination: actor_position,
+                    },
+                ));
+            }
+        }
+    }
+    // Remove the processed message
+    commands.remove(*entity);
+}
@@ -0,0 +1,51 @@
+use crate::prelude::*;
+
+/// System that moves investigating monsters towards the noise they heard. Monsters stop
+/// investigating once they can see the player, leaving the chase to the chasing system,
+/// or once they reach the noise or can't find a way to it.
+#[system]
+#[read_component(Point)]
+#[read_component(Investigating)]
+#[read_component(Sleeping)]
+#[read_component(Acting)]
+#[read_component(FieldOfView)]
+#[read_component(Health)]
+#[read_component(Player)]
+pub fn investigate(ecs: &SubWorld, commands: &mut CommandBuffer, #[resource] map: &Map) {
+    let mut player = <&Point>::query().filter(component::<Player>());
+    let player_position = *player.iter(ecs).next().unwrap();
+    let mut positions = <&Point>::query().filter(component::<Health>());
+
+    <(Entity, &Point, &Investigating, &FieldOfView)>::query()
+        .filter(component::<Acting>() & !component::<Sleeping>())
+        .iter(ecs)
+        .for_each(|(entity, position, investigating, fov)| {
+            if fov.visible_tiles.contains(&player_position) {
+                commands.remove_component::<Investigating>(*entity);
+                return;
+            }
+
+            let next_step = map
+                .path_between(*position, investigating.destination)
+                .and_then(|path| path.first().copied());
+            let destination = match next_step {
+                Some(destination) => destination,
+                None => {
+                    commands.remove_component::<Investigating>(*entity);
+                    return;
+                }
+            };
+
+            // Wait for anything in the way to move
+            if positions.iter(ecs).any(|position| *position == destination) {
+                return;
+            }
+            commands.push((
+                (),
+                WantsToMove {
+                    entity: *entity,
+                    destination,
+                },
+            ));
+        });
+}
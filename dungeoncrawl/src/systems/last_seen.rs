@@ -0,0 +1,45 @@
+use crate::prelude::*;
+
+/// System that remembers where the player last saw each entity on the map. The memory is
+/// updated while an entity is in view and forgotten once the player sees the remembered
+/// tile without the entity on it, or the entity leaves the map by being picked up.
+#[system]
+#[read_component(Point)]
+#[read_component(Render)]
+#[read_component(LastSeen)]
+#[read_component(FieldOfView)]
+#[read_component(Player)]
+pub fn last_seen(ecs: &SubWorld, commands: &mut CommandBuffer) {
+    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
+    let player_fov = fov.iter(ecs).next().unwrap();
+
+    // Remember the position of every entity in view, unless it is already remembered there
+    <(Entity, &Point, Option<&LastSeen>)>::query()
+        .filter(component::<Render>() & !component::<Player>())
+        .iter(ecs)
+        .filter(|(_, position, last_seen)| {
+            player_fov.visible_tiles.contains(position)
+                && last_seen.is_none_or(|last_seen| last_seen.position != **position)
+        })
+        .for_each(|(entity, position, _)| {
+            commands.add_component(
+                *entity,
+                LastSeen {
+                    position: *position,
+                },
+            );
+        });
+
+    // Forget entities that are no longer where they were last seen
+    <(Entity, Option<&Point>, &LastSeen)>::query()
+        .iter(ecs)
+        .filter(|(_, position, last_seen)| match position {
+            Some(position) => {
+                **position != last_seen.position
+                    && player_fov.visible_tiles.contains(&last_seen.position)
+                    && !player_fov.visible_tiles.contains(position)
+            }
+            None => true,
+        })
+        .for_each(|(entity, _, _)| commands.remove_component::<LastSeen>(*entity));
+}
@@ -0,0 +1,38 @@
+use crate::prelude::*;
+
+/// System that rebuilds the light on every tile from the light sources. The light is only
+/// rebuilt when a light source moved or changed, or the map changed shape. The player can
+/// only see tiles that are lit, so when the light changes the player's field of view is
+/// marked dirty to be narrowed to the newly lit tiles.
+#[system]
+#[read_component(Point)]
+#[read_component(LightSource)]
+#[read_component(Player)]
+#[write_component(FieldOfView)]
+pub fn lighting(ecs: &mut SubWorld, #[resource] map: &Map, #[resource] lighting: &mut Lighting) {
+    let sources: Vec<(Point, LightSource)> = <(&Point, &LightSource)>::query()
+        .iter(ecs)
+        .map(|(position, light)| (*position, *light))
+        .collect();
+    if lighting.is_current(&sources) {
+        return;
+    }
+
+    lighting.reset(sources.clone());
+    sources.iter().for_each(|(position, light)| {
+        field_of_view_set(*position, light.radius, map)
+            .iter()
+            .for_each(|tile| {
+                let distance = DistanceAlg::Pythagoras.distance2d(*position, *tile);
+                let falloff = 1.0 - distance / (light.radius + 1) as f32;
+                if falloff > 0.0 {
+                    lighting.add_light(*tile, light.color * falloff);
+                }
+            });
+    });
+
+    <&mut FieldOfView>::query()
+        .filter(component::<Player>())
+        .iter_mut(ecs)
+        .for_each(|fov| fov.is_dirty = true);
+}
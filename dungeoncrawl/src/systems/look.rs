@@ -0,0 +1,121 @@
+use crate::prelude::*;
+
+/// System that highlights the tile under the look cursor and describes it in a panel on
+/// the HUD layer. Entities are only described when the tile is currently visible. The
+/// panel names the keys bound to moving the cursor and leaving look mode.
+#[system]
+#[read_component(Point)]
+#[read_component(Name)]
+#[read_component(Unidentified)]
+#[read_component(LastSeen)]
+#[read_component(Health)]
+#[read_component(FieldOfView)]
+#[read_component(Player)]
+pub fn look(
+    ecs: &SubWorld,
+    #[resource] turn_state: &TurnState,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera,
+    #[resource] key_map: &KeyMap,
+    #[resource] options: &Options,
+) {
+    let cursor = match *turn_state {
+        TurnState::Looking(cursor) => cursor,
+        _ => return,
+    };
+
+    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
+    let player_fov = fov.iter(ecs).next().unwrap();
+
+    // Highlight the tile under the cursor on the base layer
+    let offset = Point::new(camera.left_x, camera.top_y);
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(0);
+    draw_batch.set_bg(cursor - offset, BLUE);
+    draw_batch.submit(1).expect("Batch error");
+
+    // Build the lines describing the tile under the cursor
+    let visible = player_fov.visible_tiles.contains(&cursor);
+    let revealed = map
+        .try_idx(cursor)
+        .is_some_and(|idx| map.revealed_tiles[idx]);
+    let mut lines: Vec<(String, (u8, u8, u8))> = Vec::new();
+    lines.push((format!("Position: {}, {}", cursor.x, cursor.y), WHITE));
+
+    if visible || revealed {
+        let tile = match map.tiles[map.idx(cursor.x, cursor.y)] {
+            TileType::Wall => "Wall",
+            TileType::Floor => "Floor",
+        };
+        lines.push((format!("Tile: {}", tile), WHITE));
+    } else {
+        lines.push(("Tile: Unknown".to_string(), WHITE));
+    }
+
+    if visible {
+        lines.push(("Currently visible".to_string(), GREEN));
+        <(Entity, &Point, &Name)>::query()
+            .iter(ecs)
+            .filter(|(_, position, _)| **position == cursor)
+            .for_each(|(entity, _, name)| {
+                let entry = ecs.entry_ref(*entity).unwrap();
+                let name = name.shown(entry.get_component::<Unidentified>().ok());
+                let description = if let Ok(health) = entry.get_component::<Health>() {
+                    format!("{}: {} / {} hp", name, health.current, health.max)
+                } else {
+                    name
+                };
+                lines.push((description, YELLOW));
+            });
+    } else if revealed {
+        lines.push(("Remembered".to_string(), DARK_GRAY));
+        <(&LastSeen, &Name, Option<&Unidentified>)>::query()
+            .iter(ecs)
+            .filter(|(last_seen, _, _)| last_seen.position == cursor)
+            .for_each(|(_, name, unidentified)| {
+                lines.push((format!("{} (last seen)", name.shown(unidentified)), GRAY));
+            });
+    } else {
+        lines.push(("Not yet seen".to_string(), DARK_GRAY));
+    }
+
+    // Draw the panel on the right side of the HUD layer
+    let panel_x = SCREEN_WIDTH * 2 - 42;
+    let panel_y = 4;
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+    draw_batch.draw_box(
+        Rect::with_size(panel_x, panel_y, 40, lines.len() as i32 + 6),
+        ColorPair::new(WHITE, BLACK),
+    );
+    draw_batch.print_color(
+        Point::new(panel_x + 2, panel_y),
+        " Look ",
+        ColorPair::new(YELLOW, BLACK),
+    );
+    lines.iter().enumerate().for_each(|(i, (line, color))| {
+        draw_batch.print_color(
+            Point::new(panel_x + 2, panel_y + 2 + i as i32),
+            line,
+            ColorPair::new(*color, BLACK),
+        );
+    });
+    let hints = [
+        format!(
+            "{} to move",
+            key_map.short_prompt(&MOVE_ACTIONS, options.movement_keys)
+        ),
+        format!(
+            "{} to exit",
+            key_map.prompt(&[Action::Pause, Action::Look], options.movement_keys)
+        ),
+    ];
+    hints.iter().enumerate().for_each(|(i, hint)| {
+        draw_batch.print_color(
+            Point::new(panel_x + 2, panel_y + lines.len() as i32 + 3 + i as i32),
+            hint,
+            ColorPair::new(GRAY, BLACK),
+        );
+    });
+    draw_batch.submit(10200).expect("Batch error");
+}
@@ -0,0 +1,32 @@
+use crate::prelude::*;
+
+/// System that moves the look cursor with the movement actions. The cursor is kept within
+/// the visible part of the map. The look or pause actions leave look mode without using
+/// a turn.
+#[system]
+pub fn look_input(
+    #[resource] action: &Option<Action>,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera,
+) {
+    let cursor = match *turn_state {
+        TurnState::Looking(cursor) => cursor,
+        _ => return,
+    };
+
+    if let Some(action) = *action {
+        let delta = match action {
+            Action::Pause | Action::Look => {
+                *turn_state = TurnState::AwaitingInput;
+                return;
+            }
+            _ => action.delta(),
+        };
+
+        let destination = cursor + delta;
+        if camera.contains(destination) && map.in_bounds(destination) {
+            *turn_state = TurnState::Looking(destination);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::prelude::*;
+
+/// System that brings everything depending on the shape of the map up to date with the
+/// tiles changed since it last ran, such as walls dug through. Only the fields of view that
+/// can reach a changed tile are recalculated, the light is rebuilt as it may now shine
+/// further, and travellers plan their path again as a shorter route may have opened up.
+/// Nothing is done while the map is unchanged.
+#[system]
+#[read_component(Point)]
+#[write_component(FieldOfView)]
+#[read_component(Travelling)]
+pub fn map_changes(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &mut Map,
+    #[resource] lighting: &mut Lighting,
+) {
+    let changed_tiles = map.take_changed_tiles();
+    if changed_tiles.is_empty() {
+        return;
+    }
+
+    // Mark the fields of view within reach of a changed tile dirty
+    <(&Point, &mut FieldOfView)>::query()
+        .iter_mut(ecs)
+        .filter(|(position, fov)| {
+            changed_tiles.iter().any(|tile| {
+                DistanceAlg::Pythagoras.distance2d(**position, *tile) <= fov.radius as f32 + 1.0
+            })
+        })
+        .for_each(|(_, fov)| fov.is_dirty = true);
+    lighting.mark_dirty();
+
+    // Plan the paths of travellers to their destinations again
+    <(Entity, &Point, &Travelling)>::query().iter(ecs).for_each(
+        |(entity, position, travelling)| {
+            let path = travelling
+                .path
+                .last()
+                .and_then(|destination| map.known_path_between(*position, *destination));
+            match path {
+                Some(path) => commands.add_component(
+                    *entity,
+                    Travelling {
+                        path,
+                        seen_monsters: travelling.seen_monsters.clone(),
+                    },
+                ),
+                None => commands.remove_component::<Travelling>(*entity),
+            }
+        },
+    );
+}
@@ -0,0 +1,114 @@
+use crate::prelude::*;
+
+use super::minimap::MapScale;
+
+// The size of the HUD layer, the whole of which is used for the overview
+const HUD_SIZE: Point = Point {
+    x: SCREEN_WIDTH * 2,
+    y: SCREEN_HEIGHT * 2,
+};
+
+/// System that draws the whole map centered on the HUD layer, scaled to fit and ignoring
+/// the camera. Tiles use the same visible and remembered tints as the map render, with
+/// the player and visible monsters drawn on top and a legend in the corner.
+#[system]
+#[read_component(Point)]
+#[read_component(FieldOfView)]
+#[read_component(Player)]
+#[read_component(Enemy)]
+pub fn map_overview(
+    ecs: &SubWorld,
+    #[resource] map: &Map,
+    #[resource] options: &Options,
+    #[resource] key_map: &KeyMap,
+) {
+    let mut fov = <(&Point, &FieldOfView)>::query().filter(component::<Player>());
+    let (player_position, player_fov) = fov.iter(ecs).next().unwrap();
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+
+    // Center the map on the screen
+    let scale = MapScale::fit(map, HUD_SIZE);
+    let offset = (HUD_SIZE - scale.size) / 2;
+
+    // Fill the cells covered by a map tile
+    let mut draw_tile = |point: Point, color: (u8, u8, u8), glyph: char| {
+        scale.cells(point).iter().for_each(|cell| {
+            draw_batch.set(
+                *cell + offset,
+                ColorPair::new(color, BLACK),
+                to_cp437(glyph),
+            );
+        });
+    };
+
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let point = Point::new(x, y);
+            let idx = map.idx(x, y);
+            let visible = player_fov.visible_tiles.contains(&point);
+            if !visible && !map.revealed_tiles[idx] {
+                continue;
+            }
+            let tint = if visible {
+                options.color_scheme.visible_tint()
+            } else {
+                options.color_scheme.remembered_tint()
+            };
+            match map.tiles[idx] {
+                TileType::Floor => draw_tile(point, tint, '░'),
+                TileType::Wall => draw_tile(point, tint, '█'),
+            }
+        }
+    }
+
+    <&Point>::query()
+        .filter(component::<Enemy>())
+        .iter(ecs)
+        .filter(|position| player_fov.visible_tiles.contains(position))
+        .for_each(|position| draw_tile(*position, RED, '█'));
+
+    draw_tile(*player_position, YELLOW, '█');
+
+    // Draw the legend over the bottom left corner of the map
+    let legend = [
+        ("█", options.color_scheme.visible_tint(), "Wall"),
+        ("░", options.color_scheme.visible_tint(), "Floor"),
+        ("█", YELLOW, "You"),
+        ("█", RED, "Monster in view"),
+    ];
+    let legend_height = legend.len() as i32 + 5;
+    let top = HUD_SIZE.y - legend_height - 1;
+    draw_batch.draw_box(
+        Rect::with_size(1, top, 30, legend_height),
+        ColorPair::new(WHITE, BLACK),
+    );
+    draw_batch.print_color(
+        Point::new(3, top + 1),
+        "Map Overview",
+        ColorPair::new(YELLOW, BLACK),
+    );
+    legend
+        .iter()
+        .enumerate()
+        .for_each(|(i, (glyph, color, text))| {
+            let y = top + 2 + i as i32;
+            draw_batch.print_color(Point::new(3, y), glyph, ColorPair::new(*color, BLACK));
+            draw_batch.print(Point::new(5, y), text);
+        });
+    draw_batch.print_color(
+        Point::new(3, top + 2 + legend.len() as i32),
+        "Dimmed tiles are remembered",
+        ColorPair::new(options.color_scheme.remembered_tint(), BLACK),
+    );
+    draw_batch.print(
+        Point::new(3, top + 3 + legend.len() as i32),
+        format!(
+            "{} to return",
+            key_map.prompt(&[Action::MapOverview, Action::Pause], options.movement_keys)
+        ),
+    );
+
+    draw_batch.submit(10000).expect("Batch error");
+}
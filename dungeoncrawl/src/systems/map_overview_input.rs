@@ -0,0 +1,13 @@
+use crate::prelude::*;
+
+/// System that leaves the map overview when the overview or pause actions are used.
+/// Other actions are ignored so the overview never uses a turn.
+#[system]
+pub fn map_overview_input(
+    #[resource] action: &Option<Action>,
+    #[resource] turn_state: &mut TurnState,
+) {
+    if let Some(Action::MapOverview) | Some(Action::Pause) = *action {
+        *turn_state = TurnState::AwaitingInput;
+    }
+}
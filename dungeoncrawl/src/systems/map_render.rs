@@ -3,10 +3,16 @@ use crate::prelude::*;
 #[system]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
-pub fn map_render(ecs: &SubWorld, #[resource] map: &Map, #[resource] camera: &Camera) {
+pub fn map_render(
+    ecs: &SubWorld,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera,
+    #[resource] options: &Options,
+    #[resource] lighting: &Lighting,
+) {
     // Get the field of view for the player
-    let mut fov = <(&FieldOfView)>::query().filter(component::<Player>());
-    let player_fov = fov.iter(ecs).nth(0).unwrap();
+    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
+    let player_fov = fov.iter(ecs).next().unwrap();
 
     // Create new draw batch. This will batch deferred rendering commands
     let mut draw_batch = DrawBatch::new();
@@ -20,16 +26,20 @@ pub fn map_render(ecs: &SubWorld, #[resource] map: &Map, #[resource] camera: &Ca
             let point = Point::new(x, y);
             // The edges of the current viewport
             let offset = Point::new(camera.left_x, camera.top_y);
-            // Check to see if the point on the map is in bounds and within the
-            // field of view of the player or if it has previously been revealed.
-            let idx = map_idx(x, y);
-            let tile_visible = player_fov.visible_tiles.contains(&point)
-                || (idx < 4000 && map.revealed_tiles[idx]);
-            if map.in_bounds(point) && tile_visible {
-                let tint = if player_fov.visible_tiles.contains(&point) {
-                    WHITE
+            // Skip points outside the map, the camera may show past the edges of the map
+            let idx = match map.try_idx(point) {
+                Some(idx) => idx,
+                None => continue,
+            };
+            // Check to see if the point is within the field of view of the player or if
+            // it has previously been revealed.
+            let visible = player_fov.visible_tiles.contains(&point);
+            if visible || map.revealed_tiles[idx] {
+                // Visible tiles are shaded by the light falling on them
+                let tint = if visible {
+                    lighting.tint(point, options.color_scheme.visible_tint().into())
                 } else {
-                    DARK_GRAY
+                    options.color_scheme.remembered_tint().into()
                 };
                 // Set the individual cell glyph for the position with tint
                 match map.tiles[idx] {
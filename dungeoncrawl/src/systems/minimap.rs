@@ -0,0 +1,134 @@
+use crate::prelude::*;
+
+// The largest area of the HUD layer the minimap may cover. The HUD layer has four cells
+// across and down for every map tile on screen, so this is a small panel in the corner
+// that hides only a few tiles of the play area.
+const MINIMAP_AREA: Point = Point { x: 40, y: 25 };
+
+/*
+ * Fits a map within an area of the HUD layer. Maps that are small enough are zoomed so
+ * each tile covers a square block of cells, larger maps are shrunk so each cell shows
+ * one tile out of a square block of tiles.
+ */
+pub struct MapScale {
+    zoom: i32,
+    shrink: i32,
+    // The number of cells across and down covered by the map
+    pub size: Point,
+}
+
+impl MapScale {
+    pub fn fit(map: &Map, area: Point) -> Self {
+        let zoom = i32::max(1, i32::min(area.x / map.width, area.y / map.height));
+        let shrink = i32::max(
+            (map.width + area.x - 1) / area.x,
+            (map.height + area.y - 1) / area.y,
+        );
+        Self {
+            zoom,
+            shrink,
+            size: Point::new(
+                (map.width * zoom + shrink - 1) / shrink,
+                (map.height * zoom + shrink - 1) / shrink,
+            ),
+        }
+    }
+
+    // The map tile shown in the given cell, relative to the top left of the area
+    pub fn tile(&self, cell: Point) -> Point {
+        Point::new(
+            cell.x * self.shrink / self.zoom,
+            cell.y * self.shrink / self.zoom,
+        )
+    }
+
+    // The cells covering the given map tile, relative to the top left of the area
+    pub fn cells(&self, tile: Point) -> Vec<Point> {
+        let top_left = Point::new(
+            tile.x * self.zoom / self.shrink,
+            tile.y * self.zoom / self.shrink,
+        );
+        let mut cells = Vec::new();
+        for y in 0..self.zoom {
+            for x in 0..self.zoom {
+                cells.push(top_left + Point::new(x, y));
+            }
+        }
+        cells
+    }
+}
+
+/// System that draws the whole map in a small panel in the top right corner of the HUD
+/// layer, scaled to fit. Revealed tiles are drawn dimmed with the tiles in view, the
+/// player and any visible monsters drawn on top.
+#[system]
+#[read_component(Point)]
+#[read_component(Render)]
+#[read_component(FieldOfView)]
+#[read_component(Player)]
+#[read_component(Enemy)]
+pub fn minimap(ecs: &SubWorld, #[resource] map: &Map, #[resource] options: &Options) {
+    if !options.show_minimap {
+        return;
+    }
+
+    let mut fov = <(&Point, &FieldOfView)>::query().filter(component::<Player>());
+    let (player_position, player_fov) = fov.iter(ecs).next().unwrap();
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+
+    // Frame the minimap below the health bar and hunger state. Drawing the box also
+    // clears the area inside it.
+    let scale = MapScale::fit(map, MINIMAP_AREA);
+    let offset = Point::new(SCREEN_WIDTH * 2 - scale.size.x - 1, 3);
+    draw_batch.draw_box(
+        Rect::with_size(
+            offset.x - 1,
+            offset.y - 1,
+            scale.size.x + 1,
+            scale.size.y + 1,
+        ),
+        ColorPair::new(GRAY, BLACK),
+    );
+
+    for y in 0..scale.size.y {
+        for x in 0..scale.size.x {
+            let cell = Point::new(x, y);
+            let point = scale.tile(cell);
+            let idx = match map.try_idx(point) {
+                Some(idx) => idx,
+                None => continue,
+            };
+            let visible = player_fov.visible_tiles.contains(&point);
+            if !visible && !map.revealed_tiles[idx] {
+                continue;
+            }
+            let (glyph, color) = match (map.tiles[idx], visible) {
+                (TileType::Floor, true) => ('.', GRAY),
+                (TileType::Floor, false) => ('.', DIM_GRAY),
+                (TileType::Wall, true) => ('#', LIGHT_GRAY),
+                (TileType::Wall, false) => ('#', DIM_GRAY),
+            };
+            draw_batch.set(offset + cell, ColorPair::new(color, BLACK), to_cp437(glyph));
+        }
+    }
+
+    // Only monsters in view are shown so the minimap doesn't give away their positions
+    <(&Point, &Render)>::query()
+        .filter(component::<Enemy>())
+        .iter(ecs)
+        .filter(|(position, _)| player_fov.visible_tiles.contains(position))
+        .for_each(|(position, render)| {
+            scale.cells(*position).iter().for_each(|cell| {
+                draw_batch.set(*cell + offset, ColorPair::new(RED, BLACK), render.glyph);
+            });
+        });
+
+    scale.cells(*player_position).iter().for_each(|cell| {
+        draw_batch.set(*cell + offset, ColorPair::new(YELLOW, BLACK), to_cp437('@'));
+    });
+
+    // Draw above the HUD and below the tooltips
+    draw_batch.submit(10050).expect("Batch error");
+}
@@ -1,21 +1,55 @@
 use crate::prelude::*;
 
+mod auto_explore;
+mod boss;
 mod chasing;
 mod combat;
+mod companions;
+mod dig;
 mod end_turn;
+mod energy;
 mod entity_render;
 mod fov;
 mod hud;
+mod interact;
+mod investigate;
+mod last_seen;
+mod lighting;
+mod look;
+mod look_input;
+mod map_changes;
+mod map_overview;
+mod map_overview_input;
 mod map_render;
+mod minimap;
 mod movement;
+mod noise;
+mod notice;
+mod particles;
 mod player_input;
 mod random_move;
+mod shop;
+mod shop_input;
+mod spell_menu;
+mod spell_menu_input;
+mod targeting;
+mod targeting_input;
 mod tooltips;
+mod travel;
+mod travel_preview;
+mod use_items;
 
 pub fn build_input_scheduler() -> Schedule {
     Schedule::builder()
         // Watch for player input
         .add_system(player_input::player_input_system())
+        // Take the next auto explore step if the player is exploring
+        .add_system(auto_explore::auto_explore_system())
+        // Take the next travel step or start travelling if the map was clicked
+        .add_system(travel::travel_system())
+        // Light the map. This is required here to ensure the map is lit at the
+        // beginning of a game.
+        .add_system(lighting::lighting_system())
         // Run the field of view system. This is required here to ensure the
         // field of view is calculated at the beginning of a game. There
         // should not be a dirty field of view after that point within this
@@ -23,39 +57,65 @@ pub fn build_input_scheduler() -> Schedule {
         .add_system(fov::fov_system())
         // Flush changes made as part of player input
         .flush()
+        // Remember where entities in view are and forget those no longer there
+        .add_system(last_seen::last_seen_system())
         // Render the map after the player movement
         .add_system(map_render::map_render_system())
         // Render the entities on the map after player movement
         .add_system(entity_render::entity_render_system())
         // Render the player HUD
         .add_system(hud::hud_system())
+        // Render the minimap if it is toggled on
+        .add_system(minimap::minimap_system())
         // Render the tooltips
         .add_system(tooltips::tooltips_system())
+        // Render the travel path to the tile under the mouse
+        .add_system(travel_preview::travel_preview_system())
         .build()
 }
 
 pub fn build_player_scheduler() -> Schedule {
     Schedule::builder()
+        // Process any items the player has used
+        .add_system(use_items::use_items_system())
+        // Process any interactions with non hostile entities the player bumped into
+        .add_system(interact::interact_system())
+        // Flush and process item usage
+        .flush()
         // Process Combat Messages (Player has already sent movement and attacking intent)
         .add_system(combat::combat_system())
         // Flush and process combat
         .flush()
         // Process any movement intents
         .add_system(movement::movement_system())
+        // Process any digging into the walls
+        .add_system(dig::dig_system())
         // Flush and process any movement
         .flush()
+        // Update the fields of view and paths affected by walls dug through
+        .add_system(map_changes::map_changes_system())
+        // Wake and draw monsters that hear any noise made
+        .add_system(noise::noise_system())
+        // Light the map again if any light sources moved or walls were dug through
+        .add_system(lighting::lighting_system())
         // Recalculate field of view in case player moved
         .add_system(fov::fov_system())
         // Flush and process field of view updates
         .flush()
+        // Remember where entities in view are and forget those no longer there
+        .add_system(last_seen::last_seen_system())
         // Render the map after any collisions
         .add_system(map_render::map_render_system())
         // Render the entities ontop of the map
         .add_system(entity_render::entity_render_system())
         // Render the player HUD
         .add_system(hud::hud_system())
+        // Render the minimap if it is toggled on
+        .add_system(minimap::minimap_system())
         // Render the tooltips
         .add_system(tooltips::tooltips_system())
+        // Render the travel path to the tile under the mouse
+        .add_system(travel_preview::travel_preview_system())
         // Call the end turn system to handle turn state transition
         .add_system(end_turn::end_turn_system())
         .build()
@@ -63,10 +123,25 @@ pub fn build_player_scheduler() -> Schedule {
 
 pub fn build_monster_scheduler() -> Schedule {
     Schedule::builder()
+        // Spend the energy of the monsters acting in this phase
+        .add_system(energy::energy_system())
+        .flush()
+        // Give sleeping monsters a chance to notice the player
+        .add_system(notice::notice_system())
+        // Flush so monsters that woke up can act this turn
+        .flush()
+        // Enrage the boss and summon its goblins
+        .add_system(boss::boss_system())
+        // Flush so a boss that summoned doesn't also move or attack
+        .flush()
         // Add random movement to entities tagged with MovingRandomly
         .add_system(random_move::random_move_system())
         // Add pathfinding to monsters
         .add_system(chasing::chasing_system())
+        // Move monsters towards noises they heard
+        .add_system(investigate::investigate_system())
+        // Move companions with the player and against the monsters
+        .add_system(companions::companions_system())
         // Flush any changes made as a result of a random movement
         .flush()
         // Process Combat Messages (after random movement from monsters)
@@ -74,21 +149,251 @@ pub fn build_monster_scheduler() -> Schedule {
         .flush()
         // Process any movement intents
         .add_system(movement::movement_system())
+        // Process any digging into the walls
+        .add_system(dig::dig_system())
         // Flush and process any movement
         .flush()
+        // Update the fields of view and paths affected by walls dug through
+        .add_system(map_changes::map_changes_system())
+        // Wake and draw monsters that hear any noise made
+        .add_system(noise::noise_system())
+        // Light the map again if any light sources moved or walls were dug through
+        .add_system(lighting::lighting_system())
         // Recalculate field of view for any monsters that have moved
         .add_system(fov::fov_system())
         // Flush field of view changes
         .flush()
+        // Remember where entities in view are and forget those no longer there
+        .add_system(last_seen::last_seen_system())
         // Render the map after any collisions
         .add_system(map_render::map_render_system())
         // Render the entities ontop of the map
         .add_system(entity_render::entity_render_system())
         // Render the player HUD
         .add_system(hud::hud_system())
+        // Render the minimap if it is toggled on
+        .add_system(minimap::minimap_system())
         // Render the tooltips
         .add_system(tooltips::tooltips_system())
+        // Render the travel path to the tile under the mouse
+        .add_system(travel_preview::travel_preview_system())
         // Call the end turn system to handle turn state transition
         .add_system(end_turn::end_turn_system())
         .build()
 }
+
+pub fn build_look_scheduler() -> Schedule {
+    Schedule::builder()
+        // Move the look cursor or leave look mode
+        .add_system(look_input::look_input_system())
+        // Flush changes made to the look cursor
+        .flush()
+        // Render the map under the look cursor
+        .add_system(map_render::map_render_system())
+        // Render the entities ontop of the map
+        .add_system(entity_render::entity_render_system())
+        // Render the player HUD
+        .add_system(hud::hud_system())
+        // Render the minimap if it is toggled on
+        .add_system(minimap::minimap_system())
+        // Render the look cursor and the description of what is under it
+        .add_system(look::look_system())
+        .build()
+}
+
+pub fn build_spell_menu_scheduler() -> Schedule {
+    Schedule::builder()
+        // Move the spell menu selection, cast a spell or close the menu
+        .add_system(spell_menu_input::spell_menu_input_system())
+        // Flush any spell cast
+        .flush()
+        // Render the map under the spell menu
+        .add_system(map_render::map_render_system())
+        // Render the entities ontop of the map
+        .add_system(entity_render::entity_render_system())
+        // Render the player HUD
+        .add_system(hud::hud_system())
+        // Render the minimap if it is toggled on
+        .add_system(minimap::minimap_system())
+        // Render the spell menu
+        .add_system(spell_menu::spell_menu_system())
+        .build()
+}
+
+pub fn build_targeting_scheduler() -> Schedule {
+    Schedule::builder()
+        // Move the targeting cursor, confirm the target or cancel
+        .add_system(targeting_input::targeting_input_system())
+        // Flush any item or spell used
+        .flush()
+        // Render the map under the targeting cursor
+        .add_system(map_render::map_render_system())
+        // Render the entities ontop of the map
+        .add_system(entity_render::entity_render_system())
+        // Render the player HUD
+        .add_system(hud::hud_system())
+        // Render the minimap if it is toggled on
+        .add_system(minimap::minimap_system())
+        // Render the targeting cursor and the tiles in range
+        .add_system(targeting::targeting_system())
+        .build()
+}
+
+pub fn build_shop_scheduler() -> Schedule {
+    Schedule::builder()
+        // Trade with the merchant or leave the shop
+        .add_system(shop_input::shop_input_system())
+        // Flush any trades made
+        .flush()
+        // Render the map under the shop
+        .add_system(map_render::map_render_system())
+        // Render the entities ontop of the map
+        .add_system(entity_render::entity_render_system())
+        // Render the player HUD
+        .add_system(hud::hud_system())
+        // Render the shop
+        .add_system(shop::shop_system())
+        .build()
+}
+
+pub fn build_map_overview_scheduler() -> Schedule {
+    Schedule::builder()
+        // Leave the overview when asked
+        .add_system(map_overview_input::map_overview_input_system())
+        // Render the whole map and its legend on the HUD layer
+        .add_system(map_overview::map_overview_system())
+        .build()
+}
+
+pub fn build_particle_scheduler() -> Schedule {
+    Schedule::builder()
+        // Age and render particles independently of the turn state
+        .add_system(particles::particles_system())
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use legion::systems::ParallelRunnable;
+
+    // Run a single system once over the world, flushing the commands it recorded
+    pub fn run_once<S: ParallelRunnable + 'static>(
+        system: S,
+        ecs: &mut World,
+        resources: &mut Resources,
+    ) {
+        Schedule::builder()
+            .add_system(system)
+            .build()
+            .execute(ecs, resources);
+    }
+
+    // A small open map with the player, the amulet out of reach and no monsters yet
+    fn test_game() -> (World, Resources, Entity) {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        let map = Map::new(20, 20);
+        let position = Point::new(1, 1);
+        let player = spawn_player(&mut ecs, position);
+        ecs.push((AmuletOfYala, Point::new(18, 18)));
+        resources.insert(Camera::new(position, CameraMode::Clamped, &map));
+        resources.insert(Lighting::new(&map));
+        resources.insert(TravelPreview::default());
+        resources.insert(map);
+        resources.insert(TurnState::AwaitingInput);
+        resources.insert(Options::new());
+        resources.insert(None::<Action>);
+        resources.insert(Point::zero());
+        resources.insert(MouseClick(false));
+        resources.insert(FrameTime(0.0));
+        (ecs, resources, player)
+    }
+
+    // A monster that stands still, so it does nothing with its actions but spend them
+    fn spawn_monster_with_speed(ecs: &mut World, speed: i32) -> Entity {
+        ecs.push((
+            Enemy,
+            Point::new(10, 10),
+            Health { current: 1, max: 1 },
+            Energy::new(speed),
+        ))
+    }
+
+    /*
+     * Play the given number of player turns through the player and monster schedules,
+     * counting the monster phases each of the given entities was granted an action in
+     */
+    fn actions_over(
+        turns: i32,
+        ecs: &mut World,
+        resources: &mut Resources,
+        entities: &[Entity],
+    ) -> Vec<i32> {
+        let mut player_systems = build_player_scheduler();
+        let mut monster_systems = build_monster_scheduler();
+        let mut actions = vec![0; entities.len()];
+        for _ in 0..turns {
+            resources.insert(TurnState::PlayerTurn);
+            player_systems.execute(ecs, resources);
+            while *resources.get::<TurnState>().unwrap() == TurnState::MonsterTurn {
+                monster_systems.execute(ecs, resources);
+                entities.iter().enumerate().for_each(|(i, entity)| {
+                    let entry = ecs.entry_ref(*entity).unwrap();
+                    if entry.get_component::<Acting>().is_ok() {
+                        actions[i] += 1;
+                    }
+                });
+            }
+        }
+        actions
+    }
+
+    #[test]
+    fn monsters_act_by_their_speed() {
+        let (mut ecs, mut resources, _) = test_game();
+        let normal = spawn_monster_with_speed(&mut ecs, NORMAL_SPEED);
+        let fast = spawn_monster_with_speed(&mut ecs, bat().speed);
+        let slow = spawn_monster_with_speed(&mut ecs, zombie().speed);
+        let uneven = spawn_monster_with_speed(&mut ecs, 150);
+        assert_eq!(
+            actions_over(100, &mut ecs, &mut resources, &[normal, fast, slow, uneven]),
+            vec![100, 200, 50, 150]
+        );
+    }
+
+    #[test]
+    fn hasted_and_slowed_monsters_change_the_ratio() {
+        let (mut ecs, mut resources, _) = test_game();
+        let hasted = spawn_monster_with_speed(&mut ecs, NORMAL_SPEED);
+        let slowed = spawn_monster_with_speed(&mut ecs, NORMAL_SPEED);
+        let both = spawn_monster_with_speed(&mut ecs, NORMAL_SPEED);
+        let lasting = || Hasted { turns: 1000 };
+        ecs.entry(hasted).unwrap().add_component(lasting());
+        ecs.entry(slowed)
+            .unwrap()
+            .add_component(Slowed { turns: 1000 });
+        ecs.entry(both).unwrap().add_component(lasting());
+        ecs.entry(both)
+            .unwrap()
+            .add_component(Slowed { turns: 1000 });
+        assert_eq!(
+            actions_over(100, &mut ecs, &mut resources, &[hasted, slowed, both]),
+            vec![200, 50, 100]
+        );
+    }
+
+    #[test]
+    fn hasted_player_gives_monsters_less_time() {
+        let (mut ecs, mut resources, player) = test_game();
+        ecs.entry(player)
+            .unwrap()
+            .add_component(Hasted { turns: 1000 });
+        let normal = spawn_monster_with_speed(&mut ecs, NORMAL_SPEED);
+        let fast = spawn_monster_with_speed(&mut ecs, bat().speed);
+        assert_eq!(
+            actions_over(100, &mut ecs, &mut resources, &[normal, fast]),
+            vec![50, 100]
+        );
+    }
+}
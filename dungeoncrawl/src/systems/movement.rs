@@ -1,16 +1,21 @@
 use crate::prelude::*;
 
+// How far away the player's footsteps can be heard
+const FOOTSTEP_NOISE: i32 = 3;
+
 /// System that runs for each entity that has the want WantsToMove component. Checks the
 /// intended move destination and makes the changes needed if it is a valid movement. If the
-/// entity is a Player it also updates its camera.
+/// entity is a Player it also updates its camera. Tiles the player sees are revealed by
+/// the lighting system.
 #[system(for_each)]
 #[read_component(Player)]
 #[read_component(FieldOfView)]
 pub fn movement(
     entity: &Entity,
     message: &WantsToMove,
-    #[resource] map: &mut Map,
+    #[resource] map: &Map,
     #[resource] camera: &mut Camera,
+    #[resource] options: &Options,
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
 ) {
@@ -27,11 +32,15 @@ pub fn movement(
                 // Check to see if the entity is a Player component
                 if entry.get_component::<Player>().is_ok() {
                     // The entity exists and is a player, update the players camera information
-                    camera.on_player_move(message.destination);
-                    // Use the field of view to update the set of revealed tiles
-                    fov.visible_tiles.iter().for_each(|position| {
-                        map.revealed_tiles[map_idx(position.x, position.y)] = true;
-                    });
+                    camera.on_player_move(message.destination, options.camera_mode, map);
+                    // The player's footsteps may wake nearby monsters
+                    commands.push((
+                        (),
+                        Noise {
+                            position: message.destination,
+                            radius: FOOTSTEP_NOISE,
+                        },
+                    ));
                 }
             }
         }
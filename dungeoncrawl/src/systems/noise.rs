@@ -0,0 +1,51 @@
+use crate::prelude::*;
+
+/// System that lets monsters hear noises. Sleeping monsters within earshot wake up, and
+/// they and any awake monsters that can't see the player go to investigate the noise.
+/// Noise travels along the paths through the map, so walls muffle it.
+#[system]
+#[read_component(Noise)]
+#[read_component(Point)]
+#[read_component(Enemy)]
+#[read_component(Sleeping)]
+#[read_component(FieldOfView)]
+#[read_component(Player)]
+pub fn noise(ecs: &SubWorld, commands: &mut CommandBuffer, #[resource] map: &Map) {
+    let mut player = <&Point>::query().filter(component::<Player>());
+    let player_position = *player.iter(ecs).next().unwrap();
+
+    <(Entity, &Noise)>::query()
+        .iter(ecs)
+        .for_each(|(message, noise)| {
+            let earshot = DijkstraMap::new(
+                map.width,
+                map.height,
+                &[map.idx(noise.position.x, noise.position.y)],
+                map,
+                noise.radius as f32,
+            );
+
+            <(Entity, &Point, &FieldOfView, Option<&Sleeping>)>::query()
+                .filter(component::<Enemy>())
+                .iter(ecs)
+                .filter(|(_, _, fov, sleeping)| {
+                    // Awake monsters that can see the player are already chasing them
+                    sleeping.is_some() || !fov.visible_tiles.contains(&player_position)
+                })
+                .filter(|(_, position, _, _)| {
+                    map.try_idx(**position)
+                        .is_some_and(|idx| earshot.map[idx] <= noise.radius as f32)
+                })
+                .for_each(|(entity, _, _, _)| {
+                    commands.remove_component::<Sleeping>(*entity);
+                    commands.add_component(
+                        *entity,
+                        Investigating {
+                            destination: noise.position,
+                        },
+                    );
+                });
+
+            commands.remove(*message);
+        });
+}
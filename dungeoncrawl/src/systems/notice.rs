@@ -0,0 +1,30 @@
+use crate::prelude::*;
+
+/// System that gives sleeping monsters a chance to notice the player each turn the player
+/// is in view. The chance falls with the distance to the player and with the player's
+/// stealth.
+#[system]
+#[read_component(Point)]
+#[read_component(Sleeping)]
+#[read_component(Acting)]
+#[read_component(FieldOfView)]
+#[read_component(Player)]
+#[read_component(Stealth)]
+pub fn notice(ecs: &SubWorld, commands: &mut CommandBuffer) {
+    let mut player = <(&Point, Option<&Stealth>)>::query().filter(component::<Player>());
+    let (player_position, stealth) = player.iter(ecs).next().unwrap();
+    let stealth = stealth.map_or(0, |stealth| stealth.value);
+
+    let mut rng = RandomNumberGenerator::new();
+    <(Entity, &Point, &FieldOfView)>::query()
+        .filter(component::<Sleeping>() & component::<Acting>())
+        .iter(ecs)
+        .filter(|(_, _, fov)| fov.visible_tiles.contains(player_position))
+        .for_each(|(entity, position, _)| {
+            let distance = DistanceAlg::Pythagoras.distance2d(*position, *player_position) as i32;
+            if rng.roll_dice(1, 20) > stealth + distance {
+                log("A monster noticed the player");
+                commands.remove_component::<Sleeping>(*entity);
+            }
+        });
+}
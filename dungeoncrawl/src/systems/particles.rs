@@ -0,0 +1,35 @@
+use crate::prelude::*;
+
+/// System that ages particles by the time since the previous tick, removing any that
+/// have lived out their lifetime, and draws the rest on the entity layer. It runs every
+/// tick while a game is shown so particles keep moving while waiting for input.
+#[system]
+#[write_component(Particle)]
+pub fn particles(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] frame_time: &FrameTime,
+    #[resource] camera: &Camera,
+) {
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(1);
+    let offset = Point::new(camera.left_x, camera.top_y);
+
+    <(Entity, &mut Particle)>::query()
+        .iter_mut(ecs)
+        .for_each(|(entity, particle)| {
+            particle.age_ms += frame_time.0;
+            if particle.age_ms >= particle.lifetime_ms {
+                commands.remove(*entity);
+                return;
+            }
+
+            let position = particle.current_position();
+            if camera.contains(position) {
+                draw_batch.set(position - offset, particle.color, particle.glyph);
+            }
+        });
+
+    // Draw particles over the entities they belong to
+    draw_batch.submit(5500).expect("Batch error");
+}
@@ -3,89 +3,346 @@ use crate::prelude::*;
 #[system]
 #[read_component(Point)]
 #[read_component(Player)]
-#[read_component(Enemy)]
+#[read_component(Faction)]
+#[read_component(Item)]
+#[read_component(AmuletOfYala)]
+#[read_component(Carried)]
+#[read_component(Hunger)]
+#[read_component(FieldOfView)]
+#[read_component(AutoExploring)]
+#[read_component(Travelling)]
+#[read_component(Resting)]
+#[read_component(Ranged)]
+#[read_component(Merchant)]
+#[read_component(Digger)]
 #[write_component(Health)]
+#[write_component(Gold)]
 pub fn player_input(
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
-    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] action: &Option<Action>,
     #[resource] turn_state: &mut TurnState,
+    #[resource] map: &Map,
 ) {
     // Query the set of player components and their current Point
     let mut players = <(Entity, &Point)>::query().filter(component::<Player>());
 
-    if let Some(key) = *key {
-        let delta = match key {
-            VirtualKeyCode::A => Point::new(-1, 0),
-            VirtualKeyCode::Left => Point::new(-1, 0),
-            VirtualKeyCode::D => Point::new(1, 0),
-            VirtualKeyCode::Right => Point::new(1, 0),
-            VirtualKeyCode::W => Point::new(0, -1),
-            VirtualKeyCode::Up => Point::new(0, -1),
-            VirtualKeyCode::S => Point::new(0, 1),
-            VirtualKeyCode::Down => Point::new(0, 1),
-            _ => Point::new(0, 0),
-        };
-
-        // Iterate until you find the player and its intended destionation
-        let (player_entity, destination) = players
+    if let Some(action) = *action {
+        // Find the player and its current position
+        let (player_entity, player_position) = players
             .iter(ecs)
-            .find_map(|(entity, position)| Some((*entity, *position + delta)))
+            .map(|(entity, position)| (*entity, *position))
+            .next()
             .unwrap();
 
-        let mut enemies = <(Entity, &Point)>::query().filter(component::<Enemy>());
+        // Any action interrupts auto exploring, travelling or resting
+        let player = ecs.entry_ref(player_entity).unwrap();
+        if player.get_component::<AutoExploring>().is_ok()
+            || player.get_component::<Travelling>().is_ok()
+            || player.get_component::<Resting>().is_ok()
+        {
+            commands.remove_component::<AutoExploring>(player_entity);
+            commands.remove_component::<Travelling>(player_entity);
+            commands.remove_component::<Resting>(player_entity);
+            return;
+        }
+
+        // Perform the action. Movement and waiting fall through to moving the player.
+        let delta = match action {
+            Action::Pause => {
+                *turn_state = TurnState::Paused;
+                return;
+            }
+            Action::PickUp => {
+                return pick_up_items(ecs, commands, turn_state, player_entity, player_position)
+            }
+            Action::UseItem(slot) => {
+                return use_item(
+                    slot,
+                    ecs,
+                    commands,
+                    turn_state,
+                    player_entity,
+                    player_position,
+                )
+            }
+            Action::CastSpell => {
+                *turn_state = TurnState::SpellMenu { selection: 0 };
+                return;
+            }
+            Action::AutoExplore => return start_auto_explore(ecs, commands, player_entity),
+            Action::Rest => return commands.add_component(player_entity, Resting),
+            Action::Look => {
+                *turn_state = TurnState::Looking(player_position);
+                return;
+            }
+            Action::MapOverview => {
+                *turn_state = TurnState::MapOverview;
+                return;
+            }
+            Action::MoveLeft
+            | Action::MoveRight
+            | Action::MoveUp
+            | Action::MoveDown
+            | Action::Wait => action.delta(),
+            // Other actions, such as confirm, do nothing during play and don't use a turn
+            _ => return,
+        };
+
+        // The intended destination of the player
+        let destination = player_position + delta;
+
+        // Get the entities with health that may be in the way
+        let mut creatures = <(Entity, &Point)>::query().filter(component::<Health>());
 
         let mut did_something = false;
-        // If the player has moved check to see if they are attacking an enemy
+        // If the player has moved check to see if they are attacking or interacting
         if delta.x != 0 || delta.y != 0 {
             // Track if the player moved or stayed in place
             did_something = true;
-            // Filter enemies to entities in the same position as the player just moved.
-            // If an entity is in the same position send a message of intent for attacking.
+            // Filter creatures to entities in the same position as the player just moved.
+            // Send a message of intent to attack those hostile to the player and to
+            // interact with anyone else, for example to trade with a merchant.
             let mut hit_something = false;
-            enemies
+            creatures
                 .iter(ecs)
-                .filter(|(_, position)| **position == destination)
+                .filter(|(entity, position)| **position == destination && **entity != player_entity)
                 .for_each(|(entity, _)| {
                     hit_something = true;
 
-                    commands.push((
-                        (),
-                        WantsToAttack {
-                            attacker: player_entity,
-                            victim: *entity,
-                        },
-                    ));
+                    if is_hostile(ecs, player_entity, *entity) {
+                        commands.push((
+                            (),
+                            WantsToAttack {
+                                attacker: player_entity,
+                                victim: *entity,
+                                damage: 1,
+                            },
+                        ));
+                    } else {
+                        commands.push((
+                            (),
+                            WantsToInteract {
+                                actor: player_entity,
+                                target: *entity,
+                            },
+                        ));
+                    }
                 });
 
-            // If there are no enemies in the position the player wants to move to send
-            // a messag of intent to move.
+            // Walking into a wall while carrying a digging tool digs into the wall. It
+            // takes several turns of digging to get through.
+            let dig_turns = <(&Digger, &Carried)>::query()
+                .iter(ecs)
+                .filter(|(_, carried)| carried.0 == player_entity)
+                .map(|(digger, _)| digger.turns)
+                .min()
+                .filter(|_| map.can_dig(destination));
+
+            // If there is no one in the position the player wants to move to send a
+            // message of intent to move.
             if !hit_something {
-                // Emit a WantsToMove message with the intended destination
-                // NOTE: Push doesn't work for single component insertions so add an empty component
-                // with the message.
-                commands.push((
-                    (),
-                    WantsToMove {
-                        entity: player_entity,
-                        destination,
-                    },
-                ));
+                match dig_turns {
+                    Some(turns) => {
+                        commands.push((
+                            (),
+                            WantsToDig {
+                                digger: player_entity,
+                                position: destination,
+                                turns,
+                            },
+                        ));
+                    }
+                    None => {
+                        // Emit a WantsToMove message with the intended destination
+                        // NOTE: Push doesn't work for single component insertions so add an
+                        // empty component with the message.
+                        commands.push((
+                            (),
+                            WantsToMove {
+                                entity: player_entity,
+                                destination,
+                            },
+                        ));
+                    }
+                }
             }
         }
 
-        // If the player didn't move or attack
+        // If the player didn't move or attack they rest
         if !did_something {
-            if let Ok(mut health) = ecs
-                .entry_mut(player_entity)
-                .unwrap()
-                .get_component_mut::<Health>()
-            {
-                health.current = i32::min(health.max, health.current + 1);
-                log(format!("Player healed current: {}", health.current));
+            rest(ecs, player_entity);
+        }
+
+        *turn_state = TurnState::PlayerTurn;
+    } else {
+        continue_resting(ecs, commands, turn_state);
+    }
+}
+
+/*
+ * Rest for a turn. Resting only heals the player while they are well fed.
+ */
+fn rest(ecs: &mut SubWorld, player_entity: Entity) {
+    let well_fed = ecs
+        .entry_ref(player_entity)
+        .unwrap()
+        .get_component::<Hunger>()
+        .map_or(true, |hunger| hunger.state() == HungerState::WellFed);
+    if let Ok(health) = ecs
+        .entry_mut(player_entity)
+        .unwrap()
+        .get_component_mut::<Health>()
+    {
+        if well_fed {
+            health.current = i32::min(health.max, health.current + 1);
+            log(format!("Player healed current: {}", health.current));
+        }
+    }
+}
+
+/*
+ * Take another resting turn if the player is resting. Resting stops once the player is
+ * fully healed, is too hungry to heal or anything hostile comes into view.
+ */
+fn continue_resting(ecs: &mut SubWorld, commands: &mut CommandBuffer, turn_state: &mut TurnState) {
+    let mut resting = <(Entity, &Health, &Hunger, &FieldOfView)>::query()
+        .filter(component::<Player>() & component::<Resting>());
+    let mut creatures = <(Entity, &Point)>::query().filter(component::<Health>());
+
+    let player = resting
+        .iter(ecs)
+        .map(|(entity, health, hunger, fov)| {
+            let monster_in_view = creatures.iter(ecs).any(|(creature, position)| {
+                fov.visible_tiles.contains(position) && is_hostile(ecs, *entity, *creature)
+            });
+            let done = health.current >= health.max
+                || hunger.state() != HungerState::WellFed
+                || monster_in_view;
+            (*entity, done)
+        })
+        .next();
+
+    match player {
+        Some((player_entity, false)) => {
+            rest(ecs, player_entity);
+            *turn_state = TurnState::PlayerTurn;
+        }
+        Some((player_entity, true)) => commands.remove_component::<Resting>(player_entity),
+        None => {}
+    }
+}
+
+/*
+ * Pick up any items at the players position, except for the Amulet of Yala which is
+ * claimed by walking onto it. Gold is added to the gold the player carries. Picking up
+ * an item ends the turn. If there is nothing to pick up the turn is not used.
+ */
+fn pick_up_items(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    turn_state: &mut TurnState,
+    player_entity: Entity,
+    player_position: Point,
+) {
+    let mut items =
+        <(Entity, &Point)>::query().filter(component::<Item>() & !component::<AmuletOfYala>());
+
+    let mut picked_up = false;
+    let mut gold_found = 0;
+    items
+        .iter(ecs)
+        .filter(|(_, position)| **position == player_position)
+        .for_each(|(entity, _)| {
+            picked_up = true;
+            if let Ok(gold) = ecs.entry_ref(*entity).unwrap().get_component::<Gold>() {
+                gold_found += gold.amount;
+                commands.remove(*entity);
+                return;
             }
+            // Removing the position takes the item off of the map
+            commands.remove_component::<Point>(*entity);
+            commands.add_component(*entity, Carried(player_entity));
+        });
+
+    if gold_found > 0 {
+        if let Ok(gold) = ecs
+            .entry_mut(player_entity)
+            .unwrap()
+            .get_component_mut::<Gold>()
+        {
+            gold.amount += gold_found;
         }
+    }
+
+    if picked_up {
+        *turn_state = TurnState::PlayerTurn;
+    }
+}
+
+/*
+ * Send a message of intent to use the carried item in the given inventory slot. Using
+ * an item ends the turn. Items with a range are targeted first, starting at the player.
+ * If the slot is empty the turn is not used.
+ */
+fn use_item(
+    slot: usize,
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    turn_state: &mut TurnState,
+    player_entity: Entity,
+    player_position: Point,
+) {
+    let mut carried = <(Entity, &Carried)>::query().filter(component::<Item>());
+
+    let item = carried
+        .iter(ecs)
+        .filter(|(_, carried)| carried.0 == player_entity)
+        .map(|(entity, _)| *entity)
+        .nth(slot);
 
+    if let Some(item) = item {
+        let entry = ecs.entry_ref(item).unwrap();
+        // Digging tools are used by walking into a wall while carrying them
+        if entry.get_component::<Digger>().is_ok() {
+            log("Walk into a wall to dig with the tool");
+            return;
+        }
+        if entry.get_component::<Ranged>().is_ok() {
+            *turn_state = TurnState::Targeting {
+                item,
+                cursor: player_position,
+            };
+            return;
+        }
+        commands.push((
+            (),
+            ActivateItem {
+                used_by: player_entity,
+                item,
+                target: None,
+            },
+        ));
         *turn_state = TurnState::PlayerTurn;
     }
 }
+
+/*
+ * Start auto exploring the map. The auto explore system moves the player each turn until
+ * it is interrupted. Items already in view are remembered so that only newly seen items
+ * interrupt exploring.
+ */
+fn start_auto_explore(ecs: &SubWorld, commands: &mut CommandBuffer, player_entity: Entity) {
+    let player = ecs.entry_ref(player_entity).unwrap();
+    let health = player.get_component::<Health>().unwrap().current;
+    let fov = player.get_component::<FieldOfView>().unwrap();
+
+    let seen_items = <(Entity, &Point)>::query()
+        .filter(component::<Item>())
+        .iter(ecs)
+        .filter(|(_, position)| fov.visible_tiles.contains(position))
+        .map(|(entity, _)| *entity)
+        .collect();
+
+    commands.add_component(player_entity, AutoExploring { health, seen_items });
+}
@@ -3,11 +3,15 @@ use crate::prelude::*;
 #[system]
 #[read_component(Point)]
 #[read_component(MovingRandomly)]
+#[read_component(Sleeping)]
+#[read_component(Acting)]
 #[read_component(Health)]
 #[read_component(Player)]
+#[read_component(Faction)]
 pub fn random_move(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
-    // Get all entities and their point that have the moving randomly component.
-    let mut movers = <(Entity, &Point, &MovingRandomly)>::query();
+    // Get all awake entities and their point that have the moving randomly component.
+    let mut movers = <(Entity, &Point, &MovingRandomly)>::query()
+        .filter(component::<Acting>() & !component::<Sleeping>());
     // Get the health and current point of all entities that have health.
     let mut positions = <(Entity, &Point, &Health)>::query();
 
@@ -26,28 +30,24 @@ pub fn random_move(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
             .iter(ecs)
             .filter(|(_, target_position, _)| **target_position == destination)
             .for_each(|(victim, _, _)| {
-                // Check to see the entity at the intended destination is a player.
+                // Check to see the entity at the intended destination is hostile.
                 // If it is send the message of intent to attack. Track that the entity
                 // attack to determine if it should move into the destination or not.
-                if ecs
-                    .entry_ref(*victim)
-                    .unwrap()
-                    .get_component::<Player>()
-                    .is_ok()
-                {
+                if is_hostile(ecs, *entity, *victim) {
                     commands.push((
                         (),
                         WantsToAttack {
                             attacker: *entity,
                             victim: *victim,
+                            damage: 1,
                         },
                     ));
                 }
                 attacked = true;
             });
 
-        // If the destination does not include a player that was attacked or another enemy
-        // entity send a message of intent to move to the target destination.
+        // If the destination does not include a hostile entity that was attacked or another
+        // entity in the way send a message of intent to move to the target destination.
         if !attacked {
             commands.push((
                 (),
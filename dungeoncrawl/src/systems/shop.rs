@@ -0,0 +1,112 @@
+use crate::prelude::*;
+
+/*
+ * The items the owner can trade along with the names they are shown by and the gold they
+ * change hands for. Merchants sell at the full price and buy at the sell value. Items are
+ * ordered by name so the list entries keep their places.
+ */
+pub fn wares(ecs: &SubWorld, owner: Entity, selling: bool) -> Vec<(Entity, String, i32)> {
+    let mut wares: Vec<(Entity, String, i32)> =
+        <(Entity, &Name, &Carried, &Price, Option<&Unidentified>)>::query()
+            .filter(component::<Item>())
+            .iter(ecs)
+            .filter(|(_, _, carried, _, _)| carried.0 == owner)
+            .map(|(entity, name, _, price, unidentified)| {
+                let value = if selling {
+                    price.sell_value()
+                } else {
+                    price.value
+                };
+                (*entity, name.shown(unidentified), value)
+            })
+            .collect();
+    wares.sort_by(|a, b| a.1.cmp(&b.1));
+    wares
+}
+
+/// System that draws the shop on the HUD layer. Either the merchant's stock or the
+/// player's own items are listed with their prices. Stock the player can't afford is
+/// greyed out. The hint below the list names the keys bound to using the shop.
+#[system]
+#[read_component(Item)]
+#[read_component(Name)]
+#[read_component(Carried)]
+#[read_component(Price)]
+#[read_component(Unidentified)]
+#[read_component(Gold)]
+#[read_component(Player)]
+pub fn shop(
+    ecs: &SubWorld,
+    #[resource] turn_state: &TurnState,
+    #[resource] key_map: &KeyMap,
+    #[resource] options: &Options,
+) {
+    let (merchant, selection, selling) = match *turn_state {
+        TurnState::Shop {
+            merchant,
+            selection,
+            selling,
+        } => (merchant, selection, selling),
+        _ => return,
+    };
+
+    let mut player = <(Entity, &Gold)>::query().filter(component::<Player>());
+    let (player, gold) = player.iter(ecs).next().unwrap();
+    let owner = if selling { *player } else { merchant };
+    let wares = wares(ecs, owner, selling);
+
+    // Draw the panel in the middle of the HUD layer
+    let panel_width = 60;
+    let panel_x = SCREEN_WIDTH - panel_width / 2;
+    let panel_y = 20;
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+    draw_batch.draw_box(
+        Rect::with_size(panel_x, panel_y, panel_width, wares.len() as i32 + 7),
+        ColorPair::new(WHITE, BLACK),
+    );
+    let title = if selling { " Sell " } else { " Buy " };
+    draw_batch.print_color(
+        Point::new(panel_x + 2, panel_y),
+        title,
+        ColorPair::new(YELLOW, BLACK),
+    );
+    draw_batch.print_color(
+        Point::new(panel_x + 2, panel_y + 2),
+        format!("You have {} gold", gold.amount),
+        ColorPair::new(GOLD, BLACK),
+    );
+    if wares.is_empty() {
+        draw_batch.print_color(
+            Point::new(panel_x + 2, panel_y + 4),
+            "Nothing to trade",
+            ColorPair::new(GRAY, BLACK),
+        );
+    }
+    wares.iter().enumerate().for_each(|(i, (_, name, value))| {
+        let fg = if !selling && *value > gold.amount {
+            DARK_GRAY
+        } else {
+            WHITE
+        };
+        let bg = if i == selection { BLUE } else { BLACK };
+        draw_batch.print_color(
+            Point::new(panel_x + 2, panel_y + 4 + i as i32),
+            format!("{:<44}{:>5} gold", name, value),
+            ColorPair::new(fg, bg),
+        );
+    });
+    let movement_keys = options.movement_keys;
+    draw_batch.print_color(
+        Point::new(panel_x + 2, panel_y + wares.len() as i32 + 5),
+        format!(
+            "{} to choose, {} to trade, {} to buy or sell, {} to leave",
+            key_map.short_prompt(&[Action::MoveUp, Action::MoveDown], movement_keys),
+            key_map.prompt(&[Action::Confirm], movement_keys),
+            key_map.short_prompt(&[Action::MoveLeft, Action::MoveRight], movement_keys),
+            key_map.prompt(&[Action::Pause], movement_keys)
+        ),
+        ColorPair::new(GRAY, BLACK),
+    );
+    draw_batch.submit(10200).expect("Batch error");
+}
@@ -0,0 +1,187 @@
+use crate::prelude::*;
+
+/// System that moves the shop selection, switches between buying and selling and trades
+/// the chosen item. Trading doesn't use a turn. The pause action leaves the shop.
+#[system]
+#[read_component(Item)]
+#[read_component(Name)]
+#[read_component(Carried)]
+#[read_component(Price)]
+#[read_component(Unidentified)]
+#[read_component(Player)]
+#[write_component(Gold)]
+pub fn shop_input(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] action: &Option<Action>,
+    #[resource] turn_state: &mut TurnState,
+) {
+    let (merchant, selection, selling) = match *turn_state {
+        TurnState::Shop {
+            merchant,
+            selection,
+            selling,
+        } => (merchant, selection, selling),
+        _ => return,
+    };
+
+    let mut player = <(Entity, &Gold)>::query().filter(component::<Player>());
+    let (player, gold) = player
+        .iter(ecs)
+        .map(|(entity, gold)| (*entity, gold.amount))
+        .next()
+        .unwrap();
+    let owner = if selling { player } else { merchant };
+    let wares = super::shop::wares(ecs, owner, selling);
+
+    let shop = |selection, selling| TurnState::Shop {
+        merchant,
+        selection,
+        selling,
+    };
+    match *action {
+        Some(Action::Pause) => *turn_state = TurnState::AwaitingInput,
+        Some(Action::MoveLeft) | Some(Action::MoveRight) => *turn_state = shop(0, !selling),
+        Some(Action::MoveUp) if selection > 0 => *turn_state = shop(selection - 1, selling),
+        Some(Action::MoveDown) if selection + 1 < wares.len() => {
+            *turn_state = shop(selection + 1, selling)
+        }
+        Some(Action::Confirm) => {
+            let (item, value) = match wares.get(selection) {
+                Some((item, _, value)) => (*item, *value),
+                None => return,
+            };
+            let (new_owner, change) = if selling {
+                (merchant, value)
+            } else if gold >= value {
+                (player, -value)
+            } else {
+                return;
+            };
+            commands.add_component(item, Carried(new_owner));
+            if let Ok(gold) = ecs.entry_mut(player).unwrap().get_component_mut::<Gold>() {
+                gold.amount += change;
+            }
+            // Keep the selection within the shorter list
+            *turn_state = shop(
+                usize::min(selection, wares.len().saturating_sub(2)),
+                selling,
+            );
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::tests::run_once;
+
+    #[test]
+    fn buying_pays_the_full_price() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        let player = ecs.push((Player, Gold { amount: 15 }));
+        let merchant = ecs.push((Merchant,));
+        let potion = ecs.push((
+            Item,
+            Name {
+                value: "Healing Potion".to_string(),
+            },
+            Carried(merchant),
+            Price { value: 10 },
+        ));
+        resources.insert(Some(Action::Confirm));
+        resources.insert(TurnState::Shop {
+            merchant,
+            selection: 0,
+            selling: false,
+        });
+        run_once(shop_input_system(), &mut ecs, &mut resources);
+
+        let gold = *ecs
+            .entry_ref(player)
+            .unwrap()
+            .get_component::<Gold>()
+            .unwrap();
+        let carried = *ecs
+            .entry_ref(potion)
+            .unwrap()
+            .get_component::<Carried>()
+            .unwrap();
+        assert_eq!(gold.amount, 5);
+        assert_eq!(carried.0, player);
+    }
+
+    #[test]
+    fn buying_without_enough_gold_fails() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        let player = ecs.push((Player, Gold { amount: 9 }));
+        let merchant = ecs.push((Merchant,));
+        let potion = ecs.push((
+            Item,
+            Name {
+                value: "Healing Potion".to_string(),
+            },
+            Carried(merchant),
+            Price { value: 10 },
+        ));
+        resources.insert(Some(Action::Confirm));
+        resources.insert(TurnState::Shop {
+            merchant,
+            selection: 0,
+            selling: false,
+        });
+        run_once(shop_input_system(), &mut ecs, &mut resources);
+
+        let gold = *ecs
+            .entry_ref(player)
+            .unwrap()
+            .get_component::<Gold>()
+            .unwrap();
+        let carried = *ecs
+            .entry_ref(potion)
+            .unwrap()
+            .get_component::<Carried>()
+            .unwrap();
+        assert_eq!(gold.amount, 9);
+        assert_eq!(carried.0, merchant);
+    }
+
+    #[test]
+    fn selling_earns_half_the_price() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        let player = ecs.push((Player, Gold { amount: 0 }));
+        let merchant = ecs.push((Merchant,));
+        let map = ecs.push((
+            Item,
+            Name {
+                value: "Dungeon Map".to_string(),
+            },
+            Carried(player),
+            Price { value: 10 },
+        ));
+        resources.insert(Some(Action::Confirm));
+        resources.insert(TurnState::Shop {
+            merchant,
+            selection: 0,
+            selling: true,
+        });
+        run_once(shop_input_system(), &mut ecs, &mut resources);
+
+        let gold = *ecs
+            .entry_ref(player)
+            .unwrap()
+            .get_component::<Gold>()
+            .unwrap();
+        let carried = *ecs
+            .entry_ref(map)
+            .unwrap()
+            .get_component::<Carried>()
+            .unwrap();
+        assert_eq!(gold.amount, 5);
+        assert_eq!(carried.0, merchant);
+    }
+}
@@ -0,0 +1,71 @@
+use crate::prelude::*;
+
+/*
+ * The spells known by the caster along with their names and mana costs. Spells are
+ * ordered by cost then name so the menu entries keep their places.
+ */
+pub fn known_spells(ecs: &SubWorld, caster: Entity) -> Vec<(Entity, String, i32)> {
+    let mut spells: Vec<(Entity, String, i32)> = <(Entity, &Spell, &Name, &LearnedBy)>::query()
+        .iter(ecs)
+        .filter(|(_, _, _, learned_by)| learned_by.0 == caster)
+        .map(|(entity, spell, name, _)| (*entity, name.value.clone(), spell.mana_cost))
+        .collect();
+    spells.sort_by(|a, b| a.2.cmp(&b.2).then_with(|| a.1.cmp(&b.1)));
+    spells
+}
+
+/// System that draws the spell menu on the HUD layer. Spells the player doesn't have the
+/// mana to cast are greyed out.
+#[system]
+#[read_component(Spell)]
+#[read_component(Name)]
+#[read_component(LearnedBy)]
+#[read_component(Mana)]
+#[read_component(Player)]
+pub fn spell_menu(ecs: &SubWorld, #[resource] turn_state: &TurnState) {
+    let selection = match *turn_state {
+        TurnState::SpellMenu { selection } => selection,
+        _ => return,
+    };
+
+    let mut player = <(Entity, &Mana)>::query().filter(component::<Player>());
+    let (player, mana) = player.iter(ecs).next().unwrap();
+    let spells = known_spells(ecs, *player);
+
+    // Draw the panel on the right side of the HUD layer
+    let panel_x = SCREEN_WIDTH * 2 - 42;
+    let panel_y = 4;
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+    draw_batch.draw_box(
+        Rect::with_size(panel_x, panel_y, 40, spells.len() as i32 + 5),
+        ColorPair::new(WHITE, BLACK),
+    );
+    draw_batch.print_color(
+        Point::new(panel_x + 2, panel_y),
+        " Spells ",
+        ColorPair::new(YELLOW, BLACK),
+    );
+    spells
+        .iter()
+        .enumerate()
+        .for_each(|(i, (_, name, mana_cost))| {
+            let fg = if *mana_cost > mana.current {
+                DARK_GRAY
+            } else {
+                WHITE
+            };
+            let bg = if i == selection { BLUE } else { BLACK };
+            draw_batch.print_color(
+                Point::new(panel_x + 2, panel_y + 2 + i as i32),
+                format!("{:<28}{:>3} mana", name, mana_cost),
+                ColorPair::new(fg, bg),
+            );
+        });
+    draw_batch.print_color(
+        Point::new(panel_x + 2, panel_y + spells.len() as i32 + 3),
+        "Up/Down to choose, Enter to cast",
+        ColorPair::new(GRAY, BLACK),
+    );
+    draw_batch.submit(10200).expect("Batch error");
+}
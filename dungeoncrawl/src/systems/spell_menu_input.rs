@@ -0,0 +1,70 @@
+use crate::prelude::*;
+
+/// System that moves the spell menu selection and casts the chosen spell. Spells with a
+/// range are targeted before they are cast. The pause or cast spell actions close the
+/// menu without using a turn.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Mana)]
+#[read_component(Spell)]
+#[read_component(Name)]
+#[read_component(LearnedBy)]
+#[read_component(Ranged)]
+pub fn spell_menu_input(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] action: &Option<Action>,
+    #[resource] turn_state: &mut TurnState,
+) {
+    let selection = match *turn_state {
+        TurnState::SpellMenu { selection } => selection,
+        _ => return,
+    };
+
+    let mut player = <(Entity, &Point, &Mana)>::query().filter(component::<Player>());
+    let (player, player_position, mana) = player.iter(ecs).next().unwrap();
+    let spells = super::spell_menu::known_spells(ecs, *player);
+
+    match *action {
+        Some(Action::Pause) | Some(Action::CastSpell) => *turn_state = TurnState::AwaitingInput,
+        Some(Action::MoveUp) if selection > 0 => {
+            *turn_state = TurnState::SpellMenu {
+                selection: selection - 1,
+            }
+        }
+        Some(Action::MoveDown) if selection + 1 < spells.len() => {
+            *turn_state = TurnState::SpellMenu {
+                selection: selection + 1,
+            }
+        }
+        Some(Action::Confirm) => {
+            let spell = match spells.get(selection) {
+                Some((spell, _, mana_cost)) if *mana_cost <= mana.current => *spell,
+                _ => return,
+            };
+            if ecs
+                .entry_ref(spell)
+                .unwrap()
+                .get_component::<Ranged>()
+                .is_ok()
+            {
+                *turn_state = TurnState::Targeting {
+                    item: spell,
+                    cursor: *player_position,
+                };
+            } else {
+                commands.push((
+                    (),
+                    ActivateItem {
+                        used_by: *player,
+                        item: spell,
+                        target: None,
+                    },
+                ));
+                *turn_state = TurnState::PlayerTurn;
+            }
+        }
+        _ => {}
+    }
+}
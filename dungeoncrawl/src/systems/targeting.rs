@@ -0,0 +1,79 @@
+use crate::prelude::*;
+
+/// System that highlights the tiles within range of the item or spell being targeted, the
+/// area it would hit and the tile under the targeting cursor. The cursor is green over a
+/// valid target and red otherwise. The prompt names the keys bound to aiming, confirming
+/// and cancelling.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[read_component(Ranged)]
+#[read_component(Name)]
+#[read_component(Unidentified)]
+#[read_component(AreaOfEffect)]
+#[read_component(LineOfEffect)]
+pub fn targeting(
+    ecs: &SubWorld,
+    #[resource] turn_state: &TurnState,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera,
+    #[resource] key_map: &KeyMap,
+    #[resource] options: &Options,
+) {
+    let (item, cursor) = match *turn_state {
+        TurnState::Targeting { item, cursor } => (item, cursor),
+        _ => return,
+    };
+
+    let mut player = <(&Point, &FieldOfView)>::query().filter(component::<Player>());
+    let (player_position, fov) = player.iter(ecs).next().unwrap();
+
+    // Highlight the tiles that can be targeted and the cursor on the base layer
+    let offset = Point::new(camera.left_x, camera.top_y);
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(0);
+    fov.visible_tiles
+        .iter()
+        .filter(|tile| {
+            super::targeting_input::is_valid_target(ecs, item, *player_position, fov, **tile)
+        })
+        .for_each(|tile| {
+            draw_batch.set_bg(*tile - offset, NAVY);
+        });
+    let valid = super::targeting_input::is_valid_target(ecs, item, *player_position, fov, cursor);
+    if valid {
+        super::use_items::affected_tiles(ecs, map, item, *player_position, cursor)
+            .iter()
+            .for_each(|tile| {
+                draw_batch.set_bg(*tile - offset, DARK_ORANGE);
+            });
+    }
+    draw_batch.set_bg(cursor - offset, if valid { GREEN } else { RED });
+    draw_batch.submit(1).expect("Batch error");
+
+    // Describe what is being targeted on the HUD layer
+    let name = ecs
+        .entry_ref(item)
+        .ok()
+        .and_then(|item| {
+            item.get_component::<Name>()
+                .ok()
+                .map(|name| name.shown(item.get_component::<Unidentified>().ok()))
+        })
+        .unwrap_or_default();
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+    draw_batch.print_color_centered(
+        SCREEN_HEIGHT * 2 - 2,
+        format!(
+            "Targeting {}. {} to aim, {} to confirm, {} to cancel",
+            name,
+            key_map.short_prompt(&MOVE_ACTIONS, options.movement_keys),
+            key_map.prompt(&[Action::Confirm], options.movement_keys),
+            key_map.prompt(&[Action::Pause], options.movement_keys)
+        ),
+        ColorPair::new(YELLOW, BLACK),
+    );
+    draw_batch.submit(10200).expect("Batch error");
+}
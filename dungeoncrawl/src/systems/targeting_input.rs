@@ -0,0 +1,141 @@
+use crate::prelude::*;
+
+/// System that moves the targeting cursor with the movement actions and uses the item or
+/// spell being targeted on confirm. Only visible tiles within range can be targeted. The
+/// pause action cancels targeting without using a turn.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[read_component(Ranged)]
+pub fn targeting_input(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] action: &Option<Action>,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera,
+) {
+    let (item, cursor) = match *turn_state {
+        TurnState::Targeting { item, cursor } => (item, cursor),
+        _ => return,
+    };
+
+    if let Some(action) = *action {
+        let delta = match action {
+            Action::Pause => {
+                *turn_state = TurnState::AwaitingInput;
+                return;
+            }
+            Action::Confirm => {
+                let mut player =
+                    <(Entity, &Point, &FieldOfView)>::query().filter(component::<Player>());
+                let (player, player_position, fov) = player.iter(ecs).next().unwrap();
+                if is_valid_target(ecs, item, *player_position, fov, cursor) {
+                    commands.push((
+                        (),
+                        ActivateItem {
+                            used_by: *player,
+                            item,
+                            target: Some(cursor),
+                        },
+                    ));
+                    *turn_state = TurnState::PlayerTurn;
+                }
+                return;
+            }
+            _ => action.delta(),
+        };
+
+        let destination = cursor + delta;
+        if camera.contains(destination) && map.in_bounds(destination) {
+            *turn_state = TurnState::Targeting {
+                item,
+                cursor: destination,
+            };
+        }
+    }
+}
+
+/*
+ * Check that a tile can be targeted by the item. The tile must be in view and no further
+ * from the user than the range of the item.
+ */
+pub fn is_valid_target(
+    ecs: &SubWorld,
+    item: Entity,
+    user_position: Point,
+    fov: &FieldOfView,
+    target: Point,
+) -> bool {
+    let range = ecs
+        .entry_ref(item)
+        .ok()
+        .and_then(|item| {
+            item.get_component::<Ranged>()
+                .ok()
+                .map(|ranged| ranged.range)
+        })
+        .unwrap_or(0);
+    fov.visible_tiles.contains(&target)
+        && DistanceAlg::Pythagoras.distance2d(user_position, target) <= range as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::tests::run_once;
+
+    // Confirm the target with a spell of the given range, returning the turn state after
+    fn confirm_target(range: i32, target: Point, visible: bool) -> TurnState {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        let map = Map::new(20, 20);
+        let player_position = Point::new(5, 5);
+        let mut fov = FieldOfView::new(8);
+        if visible {
+            fov.visible_tiles.insert(target);
+        }
+        ecs.push((Player, player_position, fov));
+        let spell = ecs.push((Spell { mana_cost: 1 }, Ranged { range }));
+        resources.insert(Camera::new(player_position, CameraMode::Clamped, &map));
+        resources.insert(map);
+        resources.insert(Some(Action::Confirm));
+        resources.insert(TurnState::Targeting {
+            item: spell,
+            cursor: target,
+        });
+        run_once(targeting_input_system(), &mut ecs, &mut resources);
+        let turn_state = *resources.get::<TurnState>().unwrap();
+        turn_state
+    }
+
+    #[test]
+    fn targets_within_range_are_used() {
+        assert_eq!(
+            confirm_target(3, Point::new(8, 5), true),
+            TurnState::PlayerTurn
+        );
+        // Range is measured as a straight line
+        assert_eq!(
+            confirm_target(3, Point::new(7, 7), true),
+            TurnState::PlayerTurn
+        );
+    }
+
+    #[test]
+    fn targets_out_of_range_or_view_are_refused() {
+        assert!(matches!(
+            confirm_target(3, Point::new(9, 5), true),
+            TurnState::Targeting { .. }
+        ));
+        assert!(matches!(
+            confirm_target(3, Point::new(8, 8), true),
+            TurnState::Targeting { .. }
+        ));
+        assert!(matches!(
+            confirm_target(3, Point::new(6, 5), false),
+            TurnState::Targeting { .. }
+        ));
+    }
+}
@@ -3,14 +3,17 @@ use crate::prelude::*;
 #[system]
 #[read_component(Point)]
 #[read_component(Name)]
+#[read_component(Unidentified)]
+#[read_component(LastSeen)]
 #[read_component(Health)]
+#[read_component(Sleeping)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
 pub fn tooltips(ecs: &SubWorld, #[resource] mouse_pos: &Point, #[resource] camera: &Camera) {
     // Get the list of entities with a point and name component
     let mut positions = <(Entity, &Point, &Name)>::query();
     let mut fov = <&FieldOfView>::query().filter(component::<Player>());
-    let player_fov = fov.iter(ecs).nth(0).unwrap();
+    let player_fov = fov.iter(ecs).next().unwrap();
 
     // Calculate the map position
     let offset = Point::new(camera.left_x, camera.top_y);
@@ -24,21 +27,38 @@ pub fn tooltips(ecs: &SubWorld, #[resource] mouse_pos: &Point, #[resource] camer
     positions
         .iter(ecs)
         .filter(|(_, position, _)| {
-            **position == map_position && player_fov.visible_tiles.contains(&position)
+            **position == map_position && player_fov.visible_tiles.contains(position)
         })
         .for_each(|(entity, _, name)| {
             // The mouse position is in coordinates that align with the mosters layer.
             // The tooltip layer is four times larger - multiply the mouse position by four
             // to get the screen position for the tooltip layer.
             let screen_position = *mouse_pos * 4;
-            let display =
-                if let Ok(health) = ecs.entry_ref(*entity).unwrap().get_component::<Health>() {
-                    format!("{}: {} hp", &name.value, health.current)
-                } else {
-                    name.value.clone()
-                };
+            let entry = ecs.entry_ref(*entity).unwrap();
+            let name = name.shown(entry.get_component::<Unidentified>().ok());
+            let mut display = if let Ok(health) = entry.get_component::<Health>() {
+                format!("{}: {} hp", name, health.current)
+            } else {
+                name
+            };
+            if entry.get_component::<Sleeping>().is_ok() {
+                display.push_str(" (asleep)");
+            }
             draw_batch.print(screen_position, &display);
         });
 
+    // Entities remembered on a tile out of view are shown as last seen there
+    if !player_fov.visible_tiles.contains(&map_position) {
+        <(&LastSeen, &Name, Option<&Unidentified>)>::query()
+            .iter(ecs)
+            .filter(|(last_seen, _, _)| last_seen.position == map_position)
+            .for_each(|(_, name, unidentified)| {
+                draw_batch.print(
+                    *mouse_pos * 4,
+                    format!("{} (last seen)", name.shown(unidentified)),
+                );
+            });
+    }
+
     draw_batch.submit(10100).expect("Batch error");
 }
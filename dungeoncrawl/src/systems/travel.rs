@@ -0,0 +1,105 @@
+use crate::prelude::*;
+
+/// System that handles click to travel. Clicking a revealed tile plans a path to it and
+/// the player walks one step along the path each turn. Travelling stops when anything
+/// hostile comes into view or the destination is reached.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Health)]
+#[read_component(Faction)]
+#[read_component(FieldOfView)]
+#[read_component(Travelling)]
+#[allow(clippy::too_many_arguments)]
+pub fn travel(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera,
+    #[resource] mouse_pos: &Point,
+    #[resource] mouse_click: &MouseClick,
+    #[resource] action: &Option<Action>,
+    #[resource] turn_state: &mut TurnState,
+) {
+    // Actions are handled by the player input system
+    if action.is_some() {
+        return;
+    }
+
+    let mut player = <(Entity, &Point, &FieldOfView, Option<&Travelling>)>::query()
+        .filter(component::<Player>());
+    let (player_entity, player_position, fov, travelling) = match player.iter(ecs).next() {
+        Some(player) => player,
+        None => return,
+    };
+
+    // Get the monsters hostile to the player currently in view of the player
+    let mut monsters = <(Entity, &Point)>::query().filter(component::<Health>());
+    let mut monsters_in_view = monsters
+        .iter(ecs)
+        .filter(|(entity, position)| {
+            fov.visible_tiles.contains(position) && is_hostile(ecs, *player_entity, **entity)
+        })
+        .map(|(entity, _)| *entity);
+
+    // Start travelling to the clicked tile. Monsters that are already in view are
+    // remembered so they don't interrupt travelling.
+    if mouse_click.0 {
+        let destination = *mouse_pos + Point::new(camera.left_x, camera.top_y);
+        let revealed = map
+            .try_idx(destination)
+            .is_some_and(|idx| map.revealed_tiles[idx])
+            || fov.visible_tiles.contains(&destination);
+        if !revealed {
+            return;
+        }
+        if let Some(path) = map.known_path_between(*player_position, destination) {
+            commands.remove_component::<AutoExploring>(*player_entity);
+            commands.add_component(
+                *player_entity,
+                Travelling {
+                    path,
+                    seen_monsters: monsters_in_view.collect(),
+                },
+            );
+        }
+        return;
+    }
+
+    let travelling = match travelling {
+        Some(travelling) => travelling,
+        None => return,
+    };
+
+    // Stop if a new monster came into view, the destination was reached or the next
+    // step is no longer next to the player (the last move was blocked).
+    let monster_spotted =
+        monsters_in_view.any(|entity| !travelling.seen_monsters.contains(&entity));
+    let next_step = travelling
+        .path
+        .first()
+        .filter(|step| DistanceAlg::Pythagoras.distance2d(*player_position, **step) < 1.2);
+    let next_step = match next_step {
+        Some(next_step) if !monster_spotted => *next_step,
+        _ => {
+            commands.remove_component::<Travelling>(*player_entity);
+            return;
+        }
+    };
+
+    commands.add_component(
+        *player_entity,
+        Travelling {
+            path: travelling.path[1..].to_vec(),
+            seen_monsters: travelling.seen_monsters.clone(),
+        },
+    );
+    commands.push((
+        (),
+        WantsToMove {
+            entity: *player_entity,
+            destination: next_step,
+        },
+    ));
+    *turn_state = TurnState::PlayerTurn;
+}
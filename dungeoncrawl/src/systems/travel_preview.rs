@@ -0,0 +1,48 @@
+use crate::prelude::*;
+
+/// System that draws the path the player would travel along if the tile under the mouse
+/// was clicked. The path is only planned again when the player, the tile under the mouse
+/// or the map changes.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+pub fn travel_preview(
+    ecs: &SubWorld,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera,
+    #[resource] mouse_pos: &Point,
+    #[resource] preview: &mut TravelPreview,
+) {
+    let mut player = <(&Point, &FieldOfView)>::query().filter(component::<Player>());
+    let (player_position, fov) = player.iter(ecs).next().unwrap();
+
+    // Calculate the map position
+    let offset = Point::new(camera.left_x, camera.top_y);
+    let destination = *mouse_pos + offset;
+
+    // Only preview travel to tiles the player knows about
+    let revealed = map
+        .try_idx(destination)
+        .is_some_and(|idx| map.revealed_tiles[idx])
+        || fov.visible_tiles.contains(&destination);
+    if !revealed {
+        return;
+    }
+
+    let planned_for = Some((*player_position, destination, map.revision()));
+    if preview.planned_for != planned_for {
+        preview.planned_for = planned_for;
+        preview.path = map.known_path_between(*player_position, destination);
+    }
+
+    if let Some(path) = &preview.path {
+        let mut draw_batch = DrawBatch::new();
+        // Draw on the entity layer below the entities
+        draw_batch.target(1);
+        path.iter().for_each(|step| {
+            draw_batch.set(*step - offset, ColorPair::new(YELLOW, BLACK), to_cp437('*'));
+        });
+        draw_batch.submit(4000).expect("Batch error");
+    }
+}
@@ -0,0 +1,558 @@
+use crate::prelude::*;
+
+// The lifetime of the particles lighting up the tiles hit by a targeted effect
+const AREA_FLASH_MS: f32 = 300.0;
+
+/// System that processes ActivateItem messages. Each effect an item or spell can provide
+/// is applied by its own function, which does nothing unless the item has the component
+/// for the effect. Effects apply to the entity using the item or to the area it was
+/// targeted at. Items are consumed once their charges run out, spells cost their caster
+/// mana instead and are kept.
+#[system]
+#[read_component(ActivateItem)]
+#[read_component(Point)]
+#[read_component(Faction)]
+#[read_component(Name)]
+#[read_component(Spell)]
+#[read_component(ProvidesNutrition)]
+#[read_component(ProvidesHaste)]
+#[read_component(ProvidesSlow)]
+#[read_component(ProvidesDamage)]
+#[read_component(ProvidesHealing)]
+#[read_component(ProvidesBlink)]
+#[read_component(ProvidesFear)]
+#[read_component(ProvidesCharm)]
+#[read_component(Boss)]
+#[read_component(Merchant)]
+#[read_component(TeachesSpell)]
+#[read_component(ProvidesIdentify)]
+#[read_component(ProvidesMagicMapping)]
+#[read_component(ProvidesDetectMonsters)]
+#[read_component(Unidentified)]
+#[read_component(Carried)]
+#[read_component(AreaOfEffect)]
+#[read_component(LineOfEffect)]
+#[write_component(Hunger)]
+#[write_component(Health)]
+#[write_component(Mana)]
+#[write_component(Charges)]
+pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] map: &mut Map) {
+    let activations: Vec<(Entity, ActivateItem)> = <(Entity, &ActivateItem)>::query()
+        .iter(ecs)
+        .map(|(message, activate)| (*message, *activate))
+        .collect();
+
+    activations.iter().for_each(|(message, activate)| {
+        // Remove the ActivateItem message
+        commands.remove(*message);
+        if ecs.entry_ref(activate.item).is_err() || !spend_mana(ecs, activate) {
+            return;
+        }
+
+        eat(ecs, activate);
+        heal(ecs, activate);
+        hasten(ecs, commands, activate);
+        slow(ecs, commands, activate);
+        identify(ecs, commands, activate);
+        map_magically(ecs, map, activate);
+        detect_monsters(ecs, commands, activate);
+        teach_spell(ecs, commands, activate);
+        if let Some(target) = activate.target {
+            apply_targeted(ecs, commands, map, activate, target);
+        }
+        consume(ecs, commands, activate);
+    });
+}
+
+// The effect of the given kind the item used provides, if it provides one
+fn provided<T: storage::Component + Clone>(ecs: &SubWorld, activate: &ActivateItem) -> Option<T> {
+    ecs.entry_ref(activate.item)
+        .ok()
+        .and_then(|item| item.get_component::<T>().ok().cloned())
+}
+
+/*
+ * Spells are paid for with mana and fail without enough of it. Returns whether the item
+ * can be used, which items always can.
+ */
+fn spend_mana(ecs: &mut SubWorld, activate: &ActivateItem) -> bool {
+    let spell = match provided::<Spell>(ecs, activate) {
+        Some(spell) => spell,
+        None => return true,
+    };
+    let mut caster = ecs.entry_mut(activate.used_by);
+    let mana = caster
+        .as_mut()
+        .ok()
+        .and_then(|caster| caster.get_component_mut::<Mana>().ok())
+        .filter(|mana| mana.current >= spell.mana_cost);
+    match mana {
+        Some(mana) => {
+            mana.current -= spell.mana_cost;
+            true
+        }
+        None => {
+            log("Not enough mana to cast the spell");
+            false
+        }
+    }
+}
+
+fn eat(ecs: &mut SubWorld, activate: &ActivateItem) {
+    if let Some(nutrition) = provided::<ProvidesNutrition>(ecs, activate) {
+        if let Ok(mut user) = ecs.entry_mut(activate.used_by) {
+            if let Ok(hunger) = user.get_component_mut::<Hunger>() {
+                hunger.current = i32::min(hunger.max, hunger.current + nutrition.amount);
+                log(format!("Entity ate current hunger: {}", hunger.current));
+            }
+        }
+    }
+}
+
+fn heal(ecs: &mut SubWorld, activate: &ActivateItem) {
+    if let Some(healing) = provided::<ProvidesHealing>(ecs, activate) {
+        if let Ok(mut user) = ecs.entry_mut(activate.used_by) {
+            if let Ok(health) = user.get_component_mut::<Health>() {
+                health.current = i32::min(health.max, health.current + healing.amount);
+                log(format!("Entity healed current: {}", health.current));
+            }
+        }
+    }
+}
+
+// Speed effects replace any effect of the same kind already applied
+fn hasten(ecs: &SubWorld, commands: &mut CommandBuffer, activate: &ActivateItem) {
+    if let Some(haste) = provided::<ProvidesHaste>(ecs, activate) {
+        commands.add_component(activate.used_by, Hasted { turns: haste.turns });
+    }
+}
+
+fn slow(ecs: &SubWorld, commands: &mut CommandBuffer, activate: &ActivateItem) {
+    if let Some(slow) = provided::<ProvidesSlow>(ecs, activate) {
+        commands.add_component(activate.used_by, Slowed { turns: slow.turns });
+    }
+}
+
+/*
+ * Using an item reveals what it is, and items providing identify reveal every item the
+ * user carries. Identifying an item identifies every item of the same kind.
+ */
+fn identify(ecs: &SubWorld, commands: &mut CommandBuffer, activate: &ActivateItem) {
+    let mut kinds_identified = Vec::<String>::new();
+    if provided::<Unidentified>(ecs, activate).is_some() {
+        if let Some(name) = provided::<Name>(ecs, activate) {
+            kinds_identified.push(name.value);
+        }
+    }
+    if provided::<ProvidesIdentify>(ecs, activate).is_some() {
+        <(&Name, &Carried)>::query()
+            .filter(component::<Unidentified>())
+            .iter(ecs)
+            .filter(|(_, carried)| carried.0 == activate.used_by)
+            .for_each(|(name, _)| kinds_identified.push(name.value.clone()));
+    }
+    if kinds_identified.is_empty() {
+        return;
+    }
+
+    <(Entity, &Name)>::query()
+        .filter(component::<Unidentified>())
+        .iter(ecs)
+        .filter(|(_, name)| kinds_identified.contains(&name.value))
+        .for_each(|(entity, _)| commands.remove_component::<Unidentified>(*entity));
+}
+
+fn map_magically(ecs: &SubWorld, map: &mut Map, activate: &ActivateItem) {
+    if let Some(mapping) = provided::<ProvidesMagicMapping>(ecs, activate) {
+        if let Ok(user) = ecs.entry_ref(activate.used_by) {
+            if let Ok(position) = user.get_component::<Point>() {
+                reveal_map(map, *position, mapping.radius);
+            }
+        }
+    }
+}
+
+fn detect_monsters(ecs: &SubWorld, commands: &mut CommandBuffer, activate: &ActivateItem) {
+    if let Some(detect) = provided::<ProvidesDetectMonsters>(ecs, activate) {
+        commands.add_component(
+            activate.used_by,
+            DetectingMonsters {
+                turns: detect.turns,
+            },
+        );
+    }
+}
+
+/*
+ * Teach the spell named by the item to the user. Spells that are already known are
+ * learned again, which changes nothing.
+ */
+fn teach_spell(ecs: &SubWorld, commands: &mut CommandBuffer, activate: &ActivateItem) {
+    if let Some(teaches) = provided::<TeachesSpell>(ecs, activate) {
+        <(Entity, &Name)>::query()
+            .filter(component::<Spell>())
+            .iter(ecs)
+            .filter(|(_, name)| name.value == teaches.name)
+            .for_each(|(spell, _)| commands.add_component(*spell, LearnedBy(activate.used_by)));
+    }
+}
+
+// Targeted effects apply to whatever is in the area hit
+fn apply_targeted(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    map: &Map,
+    activate: &ActivateItem,
+    target: Point,
+) {
+    let user_position = ecs
+        .entry_ref(activate.used_by)
+        .ok()
+        .and_then(|user| user.get_component::<Point>().ok().copied())
+        .unwrap_or(target);
+    let area = affected_tiles(ecs, map, activate.item, user_position, target);
+    let victims: Vec<Entity> = <(Entity, &Point)>::query()
+        .filter(component::<Health>())
+        .iter(ecs)
+        .filter(|(entity, position)| area.contains(position) && **entity != activate.used_by)
+        .map(|(entity, _)| *entity)
+        .collect();
+    spawn_area_particles(commands, &area);
+
+    damage(ecs, commands, activate, &victims);
+    frighten(ecs, commands, activate, &victims);
+    charm(ecs, commands, activate, &victims);
+    blink(ecs, commands, map, activate, target, &victims);
+}
+
+// Damage is dealt through combat like any other attack
+fn damage(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    activate: &ActivateItem,
+    victims: &[Entity],
+) {
+    if let Some(damage) = provided::<ProvidesDamage>(ecs, activate) {
+        victims.iter().for_each(|victim| {
+            commands.push((
+                (),
+                WantsToAttack {
+                    attacker: activate.used_by,
+                    victim: *victim,
+                    damage: damage.amount,
+                },
+            ));
+        });
+    }
+}
+
+// Only the victims hostile to the user are frightened away from them
+fn frighten(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    activate: &ActivateItem,
+    victims: &[Entity],
+) {
+    if let Some(fear) = provided::<ProvidesFear>(ecs, activate) {
+        victims
+            .iter()
+            .filter(|victim| is_hostile(ecs, **victim, activate.used_by))
+            .for_each(|victim| commands.add_component(*victim, Afraid { turns: fear.turns }));
+    }
+}
+
+/*
+ * Win the victims over to the side of the user. They join the faction of the user and
+ * follow them around as companions instead of going about their own business. Only the
+ * victims hostile to the user are won over, and neither the boss nor merchants can be
+ * charmed.
+ */
+fn charm(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    activate: &ActivateItem,
+    victims: &[Entity],
+) {
+    if provided::<ProvidesCharm>(ecs, activate).is_none() {
+        return;
+    }
+    let faction = ecs
+        .entry_ref(activate.used_by)
+        .ok()
+        .and_then(|user| user.get_component::<Faction>().ok().copied());
+    let faction = match faction {
+        Some(faction) => faction,
+        None => return,
+    };
+
+    victims
+        .iter()
+        .filter(|victim| is_hostile(ecs, **victim, activate.used_by))
+        .filter(|victim| {
+            !ecs.entry_ref(**victim).is_ok_and(|victim| {
+                victim.get_component::<Boss>().is_ok() || victim.get_component::<Merchant>().is_ok()
+            })
+        })
+        .for_each(|victim| {
+            commands.add_component(*victim, faction);
+            commands.add_component(*victim, Companion);
+            commands.remove_component::<Enemy>(*victim);
+            commands.remove_component::<ChasingPlayer>(*victim);
+            commands.remove_component::<MovingRandomly>(*victim);
+            commands.remove_component::<Sleeping>(*victim);
+            commands.remove_component::<Investigating>(*victim);
+            commands.remove_component::<Afraid>(*victim);
+        });
+}
+
+// Blinking moves the user to the target if nothing is standing there
+fn blink(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    map: &Map,
+    activate: &ActivateItem,
+    target: Point,
+    victims: &[Entity],
+) {
+    if provided::<ProvidesBlink>(ecs, activate).is_some()
+        && victims.is_empty()
+        && map.can_enter_tile(target)
+    {
+        commands.push((
+            (),
+            WantsToMove {
+                entity: activate.used_by,
+                destination: target,
+            },
+        ));
+    }
+}
+
+/*
+ * Use up a charge of the item, consuming it once the charges run out. Items without
+ * charges are consumed at once, spells are kept.
+ */
+fn consume(ecs: &mut SubWorld, commands: &mut CommandBuffer, activate: &ActivateItem) {
+    let spell = provided::<Spell>(ecs, activate);
+    let mut item = ecs.entry_mut(activate.item).unwrap();
+    if let Ok(charges) = item.get_component_mut::<Charges>() {
+        charges.current -= 1;
+        if charges.current < 1 {
+            commands.remove(activate.item);
+        }
+    } else if spell.is_none() {
+        commands.remove(activate.item);
+    }
+}
+
+/*
+ * The tiles hit by an item or spell used on the target. Line effects hit every tile from
+ * the user to the target and area effects every tile around the target that it can see,
+ * other effects only hit the target.
+ */
+pub fn affected_tiles(
+    ecs: &SubWorld,
+    map: &Map,
+    item: Entity,
+    user_position: Point,
+    target: Point,
+) -> Vec<Point> {
+    let item = match ecs.entry_ref(item) {
+        Ok(item) => item,
+        Err(_) => return vec![target],
+    };
+    if item.get_component::<LineOfEffect>().is_ok() {
+        map.line_between(user_position, target)
+    } else if let Ok(area) = item.get_component::<AreaOfEffect>() {
+        map.tiles_in_radius(target, area.radius)
+    } else {
+        vec![target]
+    }
+}
+
+/*
+ * Reveal the tiles of the map within the radius of the center, or the whole map if the
+ * radius is zero
+ */
+fn reveal_map(map: &mut Map, center: Point, radius: i32) {
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let in_radius = radius == 0
+                || DistanceAlg::Pythagoras.distance2d(center, Point::new(x, y)) <= radius as f32;
+            if in_radius {
+                let idx = map.idx(x, y);
+                map.reveal(idx);
+            }
+        }
+    }
+}
+
+/*
+ * Briefly light up every tile hit by a targeted effect
+ */
+fn spawn_area_particles(commands: &mut CommandBuffer, area: &[Point]) {
+    area.iter().for_each(|tile| {
+        commands.push((
+            (),
+            Particle::new(
+                *tile,
+                ColorPair::new(YELLOW, BLACK),
+                to_cp437('*'),
+                AREA_FLASH_MS,
+            ),
+        ));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::tests::run_once;
+
+    #[test]
+    fn casting_spends_mana_and_keeps_the_spell() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Map::new(10, 10));
+        let player = ecs.push((
+            Health {
+                current: 2,
+                max: 10,
+            },
+            Mana {
+                current: 7,
+                max: 10,
+            },
+        ));
+        let spell = ecs.push((Spell { mana_cost: 5 }, ProvidesHealing { amount: 3 }));
+        ecs.push((
+            (),
+            ActivateItem {
+                used_by: player,
+                item: spell,
+                target: None,
+            },
+        ));
+        run_once(use_items_system(), &mut ecs, &mut resources);
+
+        let caster = ecs.entry_ref(player).unwrap();
+        assert_eq!(caster.get_component::<Health>().unwrap().current, 5);
+        assert_eq!(caster.get_component::<Mana>().unwrap().current, 2);
+        assert!(ecs.entry_ref(spell).is_ok());
+    }
+
+    #[test]
+    fn casting_without_enough_mana_fails() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Map::new(10, 10));
+        let player = ecs.push((
+            Health {
+                current: 2,
+                max: 10,
+            },
+            Mana {
+                current: 4,
+                max: 10,
+            },
+        ));
+        let spell = ecs.push((Spell { mana_cost: 5 }, ProvidesHealing { amount: 3 }));
+        ecs.push((
+            (),
+            ActivateItem {
+                used_by: player,
+                item: spell,
+                target: None,
+            },
+        ));
+        run_once(use_items_system(), &mut ecs, &mut resources);
+
+        let caster = ecs.entry_ref(player).unwrap();
+        assert_eq!(caster.get_component::<Health>().unwrap().current, 2);
+        assert_eq!(caster.get_component::<Mana>().unwrap().current, 4);
+    }
+
+    #[test]
+    fn items_are_used_up_with_their_last_charge() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Map::new(10, 10));
+        let player = ecs.push((Health {
+            current: 2,
+            max: 10,
+        },));
+        let wand = ecs.push((
+            ProvidesHealing { amount: 1 },
+            Charges { current: 2, max: 2 },
+        ));
+        let activate = ActivateItem {
+            used_by: player,
+            item: wand,
+            target: None,
+        };
+
+        ecs.push(((), activate));
+        run_once(use_items_system(), &mut ecs, &mut resources);
+        let charges = *ecs
+            .entry_ref(wand)
+            .unwrap()
+            .get_component::<Charges>()
+            .unwrap();
+        assert_eq!(charges.current, 1);
+
+        ecs.push(((), activate));
+        run_once(use_items_system(), &mut ecs, &mut resources);
+        assert!(ecs.entry_ref(wand).is_err());
+        let health = *ecs
+            .entry_ref(player)
+            .unwrap()
+            .get_component::<Health>()
+            .unwrap();
+        assert_eq!(health.current, 4);
+    }
+
+    #[test]
+    fn only_hostile_victims_are_charmed() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Map::new(10, 10));
+        let player = ecs.push((Player, Point::new(1, 1), Faction::Player));
+        let charm = ecs.push((ProvidesCharm, AreaOfEffect { radius: 1 }));
+        let goblin = ecs.push((
+            Enemy,
+            Point::new(5, 5),
+            Health { current: 1, max: 1 },
+            Faction::Goblins,
+        ));
+        let merchant = ecs.push((
+            Merchant,
+            Point::new(5, 6),
+            Health { current: 1, max: 1 },
+            Faction::Traders,
+        ));
+        let ally = ecs.push((
+            Point::new(6, 5),
+            Health { current: 1, max: 1 },
+            Faction::Player,
+        ));
+        ecs.push((
+            (),
+            ActivateItem {
+                used_by: player,
+                item: charm,
+                target: Some(Point::new(5, 5)),
+            },
+        ));
+        run_once(use_items_system(), &mut ecs, &mut resources);
+
+        let is_companion = |entity| {
+            ecs.entry_ref(entity)
+                .unwrap()
+                .get_component::<Companion>()
+                .is_ok()
+        };
+        assert!(is_companion(goblin));
+        assert!(!is_companion(merchant));
+        assert!(!is_companion(ally));
+    }
+}
@@ -1,8 +1,38 @@
+use crate::prelude::*;
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum TurnState {
     AwaitingInput,
     PlayerTurn,
     MonsterTurn,
+    // Examining the map with a keyboard controlled cursor at the given map position
+    Looking(Point),
+    // Choosing a spell to cast, with the highlighted entry of the spell menu
+    SpellMenu {
+        selection: usize,
+    },
+    // Choosing the tile to use an item or spell on with a keyboard controlled cursor
+    Targeting {
+        item: Entity,
+        cursor: Point,
+    },
+    // Trading with a merchant, either buying their stock or selling to them, with the
+    // highlighted entry of the list of items
+    Shop {
+        merchant: Entity,
+        selection: usize,
+        selling: bool,
+    },
+    // Viewing the whole revealed map at once
+    MapOverview,
+    // The title menu shown before a game is started
+    MainMenu,
+    // The game is paused and the pause menu is shown
+    Paused,
+    // The options screen, opened from either the pause menu or the main menu
+    Options {
+        paused: bool,
+    },
     GameOver,
     Victory,
 }